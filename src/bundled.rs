@@ -0,0 +1,70 @@
+use crate::font::{Font, StaticFont};
+
+static STANDARD: StaticFont =
+    StaticFont::new("Standard.flf", include_bytes!("../fonts/Standard.flf"));
+#[cfg(feature = "embedded-fonts")]
+static SLANT: StaticFont = StaticFont::new("Slant.flf", include_bytes!("../fonts/Slant.flf"));
+#[cfg(feature = "embedded-fonts")]
+static SMALL: StaticFont = StaticFont::new("Small.flf", include_bytes!("../fonts/Small.flf"));
+#[cfg(feature = "embedded-fonts")]
+static BIG: StaticFont = StaticFont::new("Big.flf", include_bytes!("../fonts/Big.flf"));
+#[cfg(feature = "embedded-fonts")]
+static BANNER: StaticFont = StaticFont::new("Banner.flf", include_bytes!("../fonts/Banner.flf"));
+
+/// Fonts built into the crate, parsed on first use and shared after that.
+/// Only the fonts that are asked for take up heap.
+impl Font {
+    /// figlet's default font.
+    pub fn standard() -> &'static Font {
+        STANDARD.get()
+    }
+
+    #[cfg(feature = "embedded-fonts")]
+    pub fn slant() -> &'static Font {
+        SLANT.get()
+    }
+
+    #[cfg(feature = "embedded-fonts")]
+    pub fn small() -> &'static Font {
+        SMALL.get()
+    }
+
+    #[cfg(feature = "embedded-fonts")]
+    pub fn big() -> &'static Font {
+        BIG.get()
+    }
+
+    #[cfg(feature = "embedded-fonts")]
+    pub fn banner() -> &'static Font {
+        BANNER.get()
+    }
+}
+
+/// A copy of the standard font, so `Font::default()` is ready to render.
+impl Default for Font {
+    fn default() -> Self {
+        Font::standard().clone()
+    }
+}
+
+#[test]
+fn bundled_fonts() {
+    assert!(std::ptr::eq(Font::standard(), Font::standard()));
+    assert_eq!(*Font::standard(), Font::load_font("Standard.flf").unwrap());
+    #[cfg(feature = "embedded-fonts")]
+    for (font, name) in [
+        (Font::slant(), "Slant.flf"),
+        (Font::small(), "Small.flf"),
+        (Font::big(), "Big.flf"),
+        (Font::banner(), "Banner.flf"),
+    ] {
+        assert_eq!(*font, Font::load_font(name).unwrap());
+    }
+}
+
+#[test]
+fn default_font() {
+    let font = Font::default();
+    assert_eq!(font, *Font::standard());
+    assert_eq!(font.convert("Hi"), Font::standard().convert("Hi"));
+}
@@ -0,0 +1,66 @@
+use crate::font::Font;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Remembers the output of recent `convert` calls, for previewers that
+/// re-render the same few messages over and over.
+#[derive(Debug)]
+pub struct RenderCache<'a> {
+    font: &'a Font,
+    capacity: usize,
+    entries: HashMap<String, String>,
+}
+
+impl<'a> RenderCache<'a> {
+    /// A cache holding up to `capacity` messages. Once full, new messages
+    /// are rendered without being stored.
+    pub fn new(font: &'a Font, capacity: usize) -> Self {
+        RenderCache {
+            font,
+            capacity,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// `Font::convert`, borrowed from the cache or the glyph where possible.
+    pub fn convert(&mut self, message: &str) -> Cow<'_, str> {
+        let font = self.font;
+        if message.chars().nth(1).is_none() {
+            return font.convert_cow(message);
+        }
+        if !self.entries.contains_key(message) {
+            let text = font.convert(message);
+            if self.entries.len() >= self.capacity {
+                return Cow::Owned(text);
+            }
+            self.entries.insert(message.to_string(), text);
+        }
+        Cow::Borrowed(&self.entries[message])
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[test]
+fn render_cache() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let mut cache = RenderCache::new(&f, 1);
+    assert!(matches!(cache.convert("A"), Cow::Borrowed(_)));
+    assert!(cache.is_empty());
+    assert!(matches!(cache.convert("Hi"), Cow::Borrowed(_)));
+    assert_eq!(cache.convert("Hi"), f.convert("Hi"));
+    let full = cache.convert("Yo");
+    assert!(matches!(full, Cow::Owned(_)));
+    assert_eq!(full, f.convert("Yo"));
+    assert_eq!(cache.len(), 1);
+}
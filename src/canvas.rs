@@ -0,0 +1,114 @@
+use crate::figure::FIGure;
+use crate::rules::Rules;
+use std::fmt;
+
+/// A fixed-size grid onto which several FIGures can be composited.
+#[derive(Debug)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    cells: Vec<Vec<char>>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            width,
+            height,
+            cells: vec![vec![' '; width]; height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Draw `figure` with its top left corner at (x, y). Spaces in the figure
+    /// are transparent and anything outside the canvas is clipped.
+    pub fn blit(&mut self, figure: &FIGure, x: usize, y: usize) {
+        blit(&mut self.cells, figure, x, y, None);
+    }
+
+    /// Like `blit`, but where a stroke lands on an existing stroke the two
+    /// are smushed with `rules`. Pairs the rules can't smush keep the new
+    /// stroke.
+    pub fn blit_smushed(
+        &mut self,
+        figure: &FIGure,
+        x: usize,
+        y: usize,
+        rules: &Rules,
+        hardblank: char,
+    ) {
+        blit(&mut self.cells, figure, x, y, Some((rules, hardblank)));
+    }
+
+    pub fn into_figure(self) -> FIGure {
+        FIGure::new(self.cells)
+    }
+}
+
+impl fmt::Display for Canvas {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.cells.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for c in row {
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn blit(
+    cells: &mut [Vec<char>],
+    figure: &FIGure,
+    x: usize,
+    y: usize,
+    smush: Option<(&Rules, char)>,
+) {
+    for (src, dst) in figure.rows().iter().zip(cells.iter_mut().skip(y)) {
+        for (&c, cell) in src.iter().zip(dst.iter_mut().skip(x)) {
+            if c == ' ' {
+                continue;
+            }
+            *cell = match smush {
+                Some((rules, hardblank)) if *cell != ' ' => {
+                    rules.smush_horizontal(*cell, c, hardblank).unwrap_or(c)
+                }
+                _ => c,
+            };
+        }
+    }
+}
+
+#[test]
+fn blit_transparent() {
+    let mut canvas = Canvas::new(6, 3);
+    let a = FIGure::new(vec!["ab".chars().collect(), "c d".chars().collect()]);
+    canvas.blit(&a, 0, 0);
+    canvas.blit(&a, 1, 1);
+    canvas.blit(&a, 5, 2);
+    assert_eq!(canvas.to_string(), "ab    \ncab   \n c d a");
+}
+
+#[test]
+fn blit_smushed() {
+    use crate::layout::{LayoutMode, SmushingRule};
+    let rules = Rules {
+        horizontal_layout: LayoutMode::ControlledSmush,
+        vertical_layout: LayoutMode::FullWidth,
+        horizontal_rules: vec![SmushingRule::HorizontalBigX],
+        vertical_rules: vec![],
+    };
+    let mut canvas = Canvas::new(2, 1);
+    canvas.blit(&FIGure::new(vec![vec!['/', '>']]), 0, 0);
+    canvas.blit_smushed(&FIGure::new(vec![vec!['\\', '|']]), 0, 0, &rules, '$');
+    assert_eq!(canvas.to_string(), "||");
+}
@@ -0,0 +1,399 @@
+#[cfg(feature = "toml")]
+use crate::error::ThemeError;
+use crate::figure::FIGure;
+use std::io::IsTerminal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// Parse `#rrggbb` or `rrggbb`.
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Color::rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// One of the basic terminal color names, e.g. "red" or "cyan".
+    pub fn from_name(name: &str) -> Option<Color> {
+        let color = match name.to_ascii_lowercase().as_str() {
+            "black" => Color::rgb(0x00, 0x00, 0x00),
+            "red" => Color::rgb(0xcd, 0x00, 0x00),
+            "green" => Color::rgb(0x00, 0xcd, 0x00),
+            "yellow" => Color::rgb(0xcd, 0xcd, 0x00),
+            "blue" => Color::rgb(0x00, 0x00, 0xee),
+            "magenta" => Color::rgb(0xcd, 0x00, 0xcd),
+            "cyan" => Color::rgb(0x00, 0xcd, 0xcd),
+            "white" => Color::rgb(0xe5, 0xe5, 0xe5),
+            "gray" | "grey" => Color::rgb(0x7f, 0x7f, 0x7f),
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    /// The 24-bit ANSI escape that sets this as the foreground color.
+    pub fn ansi_fg(self) -> String {
+        format!("\x1b[38;2;{};{};{}m", self.r, self.g, self.b)
+    }
+
+    /// The 24-bit ANSI escape that sets this as the background color.
+    pub fn ansi_bg(self) -> String {
+        format!("\x1b[48;2;{};{};{}m", self.r, self.g, self.b)
+    }
+}
+
+pub(crate) const RESET: &str = "\x1b[0m";
+
+/// Whether output should be colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal, unless `NO_COLOR` is set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// The choice for "auto", "always" or "never".
+    pub fn from_name(name: &str) -> Option<ColorChoice> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    /// Whether to color what's written to stdout. Following the `NO_COLOR`
+    /// convention, any non-empty value turns `Auto` off.
+    pub fn should_color(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Get the terminal ready for the ANSI escapes that `paint` writes, and
+/// say whether it will understand them. Windows consoles only do once
+/// virtual terminal processing is switched on for stdout, which is tried
+/// here; anywhere else this does nothing.
+pub fn enable_ansi() -> bool {
+    #[cfg(windows)]
+    {
+        windows::enable_virtual_terminal()
+    }
+    #[cfg(not(windows))]
+    {
+        true
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+
+    pub(super) fn enable_virtual_terminal() -> bool {
+        // SAFETY: plain console calls on the process's own stdout handle,
+        // which is checked before use.
+        unsafe {
+            let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+            if handle.is_null() || handle as isize == -1 {
+                return false;
+            }
+            let mut mode = 0;
+            if GetConsoleMode(handle, &mut mode) == 0 {
+                // Not a console, e.g. a pipe or a file, so the escapes are
+                // passed on untouched for whatever reads them.
+                return true;
+            }
+            mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+        }
+    }
+}
+
+/// How one span of input is drawn by `Renderer::render_spans`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+}
+
+impl Style {
+    /// A style that only sets the foreground color.
+    pub fn fg(color: Color) -> Self {
+        Style {
+            fg: Some(color),
+            ..Style::default()
+        }
+    }
+
+    /// The ANSI escapes that switch to this style, empty for the default.
+    pub fn ansi(self) -> String {
+        let mut escape = String::new();
+        if self.bold {
+            escape.push_str("\x1b[1m");
+        }
+        if let Some(fg) = self.fg {
+            escape.push_str(&fg.ansi_fg());
+        }
+        if let Some(bg) = self.bg {
+            escape.push_str(&bg.ansi_bg());
+        }
+        escape
+    }
+}
+
+/// A figure rendered from styled spans, knowing which span inked each cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyledFIGure {
+    pub figure: FIGure,
+    /// Per row and column, the index into `styles` of the span that drew
+    /// the cell, or `None` for blank cells.
+    pub spans: Vec<Vec<Option<usize>>>,
+    pub styles: Vec<Style>,
+}
+
+impl StyledFIGure {
+    /// The style of the cell at `row`, `col`, if a span drew it.
+    pub fn style_at(&self, row: usize, col: usize) -> Option<Style> {
+        let span = (*self.spans.get(row)?.get(col)?)?;
+        self.styles.get(span).copied()
+    }
+
+    /// Render as text with ANSI escapes wherever the style changes. Blank
+    /// cells are left plain, and every row ends reset.
+    pub fn paint(&self) -> String {
+        let rows: Vec<String> = self
+            .figure
+            .rows()
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let mut text = String::new();
+                let mut current = Style::default();
+                for (x, &c) in row.iter().enumerate() {
+                    let style = self.style_at(y, x).unwrap_or_default();
+                    if style != current {
+                        if current != Style::default() {
+                            text.push_str(RESET);
+                        }
+                        text.push_str(&style.ansi());
+                        current = style;
+                    }
+                    text.push(c);
+                }
+                if current != Style::default() {
+                    text.push_str(RESET);
+                }
+                text
+            })
+            .collect();
+        rows.join("\n")
+    }
+}
+
+/// A named palette spread over a banner's rows from top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    pub palette: Vec<Color>,
+}
+
+const BUILTIN: [(&str, &[Color]); 4] = [
+    (
+        "matrix-green",
+        &[
+            Color::rgb(0xaa, 0xff, 0xaa),
+            Color::rgb(0x33, 0xff, 0x33),
+            Color::rgb(0x00, 0xcc, 0x00),
+            Color::rgb(0x00, 0x88, 0x00),
+        ],
+    ),
+    (
+        "fire",
+        &[
+            Color::rgb(0xff, 0xff, 0x66),
+            Color::rgb(0xff, 0xcc, 0x00),
+            Color::rgb(0xff, 0x88, 0x00),
+            Color::rgb(0xff, 0x44, 0x00),
+            Color::rgb(0xcc, 0x00, 0x00),
+        ],
+    ),
+    (
+        "ocean",
+        &[
+            Color::rgb(0x99, 0xee, 0xff),
+            Color::rgb(0x33, 0xbb, 0xee),
+            Color::rgb(0x00, 0x77, 0xcc),
+            Color::rgb(0x00, 0x44, 0x99),
+        ],
+    ),
+    (
+        "grayscale",
+        &[
+            Color::rgb(0xee, 0xee, 0xee),
+            Color::rgb(0xbb, 0xbb, 0xbb),
+            Color::rgb(0x88, 0x88, 0x88),
+            Color::rgb(0x55, 0x55, 0x55),
+        ],
+    ),
+];
+
+impl Theme {
+    pub fn new(name: &str, palette: Vec<Color>) -> Self {
+        Theme {
+            name: name.to_string(),
+            palette,
+        }
+    }
+
+    /// Names of the built-in themes.
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN.iter().map(|(name, _)| *name)
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        BUILTIN
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(name, palette)| Theme::new(name, palette.to_vec()))
+    }
+
+    /// Load a theme from TOML of the form
+    /// `name = "brand"` / `palette = ["#ff0000", "#00ff00"]`.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(data: &str) -> Result<Theme, ThemeError> {
+        #[derive(serde::Deserialize)]
+        struct ThemeFile {
+            name: String,
+            palette: Vec<String>,
+        }
+
+        let file: ThemeFile = toml::from_str(data)?;
+        let palette = file
+            .palette
+            .iter()
+            .map(|hex| Color::from_hex(hex).ok_or_else(|| ThemeError::BadColor(hex.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        if palette.is_empty() {
+            return Err(ThemeError::EmptyPalette);
+        }
+        Ok(Theme::new(&file.name, palette))
+    }
+
+    /// The color for row `y` of a banner `height` rows tall.
+    pub fn row_color(&self, y: usize, height: usize) -> Option<Color> {
+        if self.palette.is_empty() || height == 0 {
+            return None;
+        }
+        self.palette.get(y * self.palette.len() / height).copied()
+    }
+
+    /// Render `figure` as text with ANSI colors. Blank rows are left plain.
+    pub fn paint(&self, figure: &FIGure) -> String {
+        let height = figure.height();
+        let rows: Vec<String> = figure
+            .rows()
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let text: String = row.iter().collect();
+                match self.row_color(y, height) {
+                    Some(color) if !text.trim().is_empty() => {
+                        format!("{}{}{}", color.ansi_fg(), text, RESET)
+                    }
+                    _ => text,
+                }
+            })
+            .collect();
+        rows.join("\n")
+    }
+}
+
+#[test]
+fn themes() {
+    assert_eq!(Color::from_hex("#ff8800"), Some(Color::rgb(255, 136, 0)));
+    assert_eq!(Color::from_hex("ff88"), None);
+    assert_eq!(Color::from_hex("#gg0000"), None);
+
+    for name in Theme::builtin_names() {
+        assert!(!Theme::builtin(name).unwrap().palette.is_empty());
+    }
+    assert_eq!(Theme::builtin("plaid"), None);
+
+    let theme = Theme::new("duo", vec![Color::rgb(1, 2, 3), Color::rgb(4, 5, 6)]);
+    let figure = FIGure::new(vec![vec!['a'], vec![' '], vec!['b'], vec!['c']]);
+    assert_eq!(
+        theme.paint(&figure),
+        "\x1b[38;2;1;2;3ma\x1b[0m\n \n\x1b[38;2;4;5;6mb\x1b[0m\n\x1b[38;2;4;5;6mc\x1b[0m"
+    );
+}
+
+#[test]
+fn styled_paint() {
+    let red = Style::fg(Color::rgb(255, 0, 0));
+    let bold = Style {
+        bold: true,
+        ..Style::default()
+    };
+    let styled = StyledFIGure {
+        figure: FIGure::new(vec!["ab c".chars().collect(), "d".chars().collect()]),
+        spans: vec![vec![Some(0), Some(0), None, Some(1)], vec![None]],
+        styles: vec![red, bold],
+    };
+    assert_eq!(styled.style_at(0, 3), Some(bold));
+    assert_eq!(styled.style_at(1, 0), None);
+    assert_eq!(
+        styled.paint(),
+        "\x1b[38;2;255;0;0mab\x1b[0m \x1b[1mc\x1b[0m\nd"
+    );
+}
+
+#[test]
+fn color_choice() {
+    assert_eq!(ColorChoice::from_name("Never"), Some(ColorChoice::Never));
+    assert_eq!(ColorChoice::from_name("sometimes"), None);
+    assert!(ColorChoice::Always.should_color());
+    assert!(!ColorChoice::Never.should_color());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn theme_from_toml() {
+    let theme = Theme::from_toml("name = \"brand\"\npalette = [\"#102030\"]").unwrap();
+    assert_eq!(
+        theme,
+        Theme::new("brand", vec![Color::rgb(0x10, 0x20, 0x30)])
+    );
+    assert!(Theme::from_toml("name = \"x\"\npalette = [\"red\"]").is_err());
+    assert!(Theme::from_toml("name = \"x\"\npalette = []").is_err());
+}
@@ -0,0 +1,188 @@
+use crate::figure::FIGure;
+use crate::font::{Compose, Font, Scratch};
+use crate::glyph::Glyph;
+use crate::layout::{LayoutMode, SmushingRule};
+use crate::rules::Rules;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// How the right glyph of a junction was brought up against the left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Join {
+    /// Set at its full width, without overlapping.
+    FullWidth,
+    /// Moved in until the two touched, without merging any strokes.
+    Fitting,
+    /// Moved in far enough that strokes in `smushes` were merged.
+    Smushing,
+}
+
+impl fmt::Display for Join {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Join::FullWidth => "full width",
+            Join::Fitting => "fitting",
+            Join::Smushing => "smushing",
+        })
+    }
+}
+
+/// What happened where two neighbouring glyphs were joined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Junction {
+    pub left: char,
+    pub right: char,
+    /// Output column where the right glyph starts.
+    pub column: usize,
+    pub overlap: usize,
+    pub join: Join,
+    /// Rows in which two strokes were smushed, and the rule that did it.
+    pub smushes: Vec<(usize, SmushingRule)>,
+}
+
+impl fmt::Display for Junction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} + {:?} at column {}: overlap {}, {}",
+            self.left, self.right, self.column, self.overlap, self.join
+        )?;
+        for (row, rule) in self.smushes.iter() {
+            write!(f, ", row {} {}", row, rule)?;
+        }
+        Ok(())
+    }
+}
+
+/// The junction where `glyph`, standing for `right`, is about to be laid
+/// over the last `overlap` columns of `rows`, which end with `left`. Units
+/// are glyph codes with the character they stand for, if any.
+pub(crate) fn junction(
+    rules: &Rules,
+    hardblank: char,
+    rows: &[Vec<char>],
+    glyph: &Glyph,
+    overlap: usize,
+    left: (i32, Option<char>),
+    right: (i32, Option<char>),
+) -> Junction {
+    let mut smushes = vec![];
+    for (row, (cs1, cs2)) in rows.iter().zip(glyph.rows()).enumerate() {
+        for (k, &c2) in cs2.iter().enumerate().take(overlap) {
+            let c1 = cs1[cs1.len() - overlap + k];
+            if c1 == ' ' || c2 == ' ' {
+                continue;
+            }
+            if let Some(rule) = rules.horizontal_rule(c1, c2, hardblank) {
+                smushes.push((row, rule));
+            }
+        }
+    }
+    let join = if !smushes.is_empty() {
+        Join::Smushing
+    } else if rules.horizontal_layout == LayoutMode::FullWidth {
+        Join::FullWidth
+    } else {
+        Join::Fitting
+    };
+    // Ligatures and stand-ins for missing characters are shown by code.
+    let shown = |(code, c): (i32, Option<char>)| {
+        c.or_else(|| u32::try_from(code).ok().and_then(char::from_u32))
+            .unwrap_or(char::REPLACEMENT_CHARACTER)
+    };
+    Junction {
+        left: shown(left),
+        right: shown(right),
+        column: rows.first().map_or(0, Vec::len) - overlap,
+        overlap,
+        join,
+        smushes,
+    }
+}
+
+impl Font {
+    /// Render `message` as `render_raw` would, recording every junction.
+    pub fn render_debug(&self, message: &str) -> (FIGure, Vec<Junction>) {
+        let options = Compose {
+            rtl: self.font_head.print_direction() == 1,
+            ..Compose::default()
+        };
+        let mut rows = vec![];
+        let mut junctions = vec![];
+        self.compose_into(
+            message,
+            &options,
+            &mut rows,
+            &mut Scratch::default(),
+            None,
+            Some(&mut junctions),
+        );
+        (FIGure::new(rows), junctions)
+    }
+
+    /// Render `message` with a marker row under every glyph junction,
+    /// followed by one line per junction describing the overlap and rules.
+    pub fn debug_report(&self, message: &str) -> String {
+        let (figure, junctions) = self.render_debug(message);
+        let mut markers = vec![' '; figure.width()];
+        for junction in junctions.iter() {
+            if let Some(m) = markers.get_mut(junction.column) {
+                *m = '^';
+            }
+        }
+        let mut report = figure.to_string();
+        report.push('\n');
+        report.extend(markers);
+        for junction in junctions.iter() {
+            report.push('\n');
+            report.push_str(&junction.to_string());
+        }
+        report
+    }
+}
+
+#[test]
+fn render_debug() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let (figure, junctions) = f.render_debug("FIG");
+    assert_eq!(figure.to_string(), f.render_raw("FIG").to_string());
+    assert_eq!(junctions.len(), 2);
+    assert_eq!(junctions[0].left, 'F');
+    assert_eq!(junctions[0].right, 'I');
+    assert!(junctions.iter().all(|j| j.overlap > 0));
+    assert!(junctions
+        .iter()
+        .flat_map(|j| j.smushes.iter())
+        .all(|(_, rule)| f.rules.horizontal_rules.contains(rule)));
+    // Characters the font lacks are left out, as when rendering.
+    let (figure, junctions) = f.render_debug("a\u{1f680}b\t");
+    assert_eq!(figure, f.render_raw("a\u{1f680}b\t"));
+    assert_eq!((junctions[0].left, junctions[0].right), ('a', 'b'));
+
+    // The strokes of "F" and "I" merge in Standard.
+    assert_eq!(junctions[0].join, Join::Smushing);
+    assert!(junctions[0].to_string().contains("smushing"));
+    let mut full = f.clone();
+    full.rules.horizontal_layout = LayoutMode::FullWidth;
+    let (_, junctions) = full.render_debug("FIG");
+    assert!(junctions
+        .iter()
+        .all(|j| j.join == Join::FullWidth && j.overlap == 0));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn junctions_as_json() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let (_, junctions) = f.render_debug("FI");
+    let json = serde_json::to_value(&junctions).unwrap();
+    assert_eq!(json[0]["left"], "F");
+    assert_eq!(json[0]["join"], "smushing");
+    assert_eq!(json[0]["overlap"], junctions[0].overlap);
+}
@@ -0,0 +1,275 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::num::ParseIntError;
+use std::str::Utf8Error;
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(io::Error),
+    ParseInt(ParseIntError),
+    Utf8(Utf8Error),
+    GlyphHeight {
+        code: i32,
+        expected: usize,
+        found: usize,
+    },
+    BadCodeTag {
+        line: usize,
+    },
+    /// The font asked for more than `ParseOptions::limits` allows.
+    TooLarge {
+        what: FontLimit,
+        value: usize,
+        limit: usize,
+    },
+    /// A TrueType or OpenType font that couldn't be read.
+    #[cfg(feature = "ttf")]
+    Ttf(String),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Io(e) => write!(f, "{}", e),
+            FontError::ParseInt(e) => write!(f, "invalid font header: {}", e),
+            FontError::Utf8(e) => write!(f, "font is not valid UTF-8: {}", e),
+            FontError::GlyphHeight {
+                code,
+                expected,
+                found,
+            } => write!(
+                f,
+                "glyph {} has {} rows, expected {}",
+                code, found, expected
+            ),
+            FontError::BadCodeTag { line } => write!(f, "line {}: invalid code tag", line),
+            FontError::TooLarge { what, value, limit } => {
+                write!(f, "font has more than {} {} ({})", limit, what, value)
+            }
+            #[cfg(feature = "ttf")]
+            FontError::Ttf(e) => write!(f, "invalid TrueType font: {}", e),
+        }
+    }
+}
+
+/// What a font had too much of, in `FontError::TooLarge`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontLimit {
+    Height,
+    MaxLength,
+    Codetags,
+    GlyphBytes,
+}
+
+impl fmt::Display for FontLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FontLimit::Height => "rows per glyph",
+            FontLimit::MaxLength => "columns of max_length",
+            FontLimit::Codetags => "code-tagged glyphs",
+            FontLimit::GlyphBytes => "bytes of glyphs",
+        })
+    }
+}
+
+impl Error for FontError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FontError::Io(e) => Some(e),
+            FontError::ParseInt(e) => Some(e),
+            FontError::Utf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FontError {
+    fn from(e: io::Error) -> Self {
+        FontError::Io(e)
+    }
+}
+
+impl From<ParseIntError> for FontError {
+    fn from(e: ParseIntError) -> Self {
+        FontError::ParseInt(e)
+    }
+}
+
+impl From<Utf8Error> for FontError {
+    fn from(e: Utf8Error) -> Self {
+        FontError::Utf8(e)
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    BadColor(String),
+    EmptyPalette,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "toml")]
+            ThemeError::Toml(e) => write!(f, "invalid theme: {}", e),
+            ThemeError::BadColor(color) => write!(f, "invalid color: {}", color),
+            ThemeError::EmptyPalette => write!(f, "theme has no colors"),
+        }
+    }
+}
+
+impl Error for ThemeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "toml")]
+            ThemeError::Toml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for ThemeError {
+    fn from(e: toml::de::Error) -> Self {
+        ThemeError::Toml(e)
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    AnsiEscape { offset: usize },
+    WordTooWide { word: String, width: usize },
+    Cancelled,
+    TooLarge { width: usize, height: usize },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderError::AnsiEscape { offset } => {
+                write!(f, "input contains an ANSI escape at byte {}", offset)
+            }
+            RenderError::WordTooWide { word, width } => {
+                write!(f, "{:?} does not fit in {} columns", word, width)
+            }
+            RenderError::Cancelled => write!(f, "render cancelled"),
+            RenderError::TooLarge { width, height } => {
+                write!(f, "output of {}x{} exceeds the limits", width, height)
+            }
+        }
+    }
+}
+
+impl Error for RenderError {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    UnknownMode(String),
+    UnknownRule(String),
+    Empty,
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayoutError::UnknownMode(name) => write!(f, "unknown layout mode: {}", name),
+            LayoutError::UnknownRule(name) => write!(f, "unknown smushing rule: {}", name),
+            LayoutError::Empty => write!(f, "no layout given"),
+        }
+    }
+}
+
+impl Error for LayoutError {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MarkupError {
+    UnknownTag { tag: String, offset: usize },
+    UnmatchedClose { tag: String, offset: usize },
+    Unterminated { offset: usize },
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MarkupError::UnknownTag { tag, offset } => {
+                write!(f, "unknown tag {{{}}} at byte {}", tag, offset)
+            }
+            MarkupError::UnmatchedClose { tag, offset } => {
+                write!(f, "{{/{}}} at byte {} closes no open tag", tag, offset)
+            }
+            MarkupError::Unterminated { offset } => {
+                write!(f, "tag at byte {} is missing its closing brace", offset)
+            }
+        }
+    }
+}
+
+impl Error for MarkupError {}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Font(FontError),
+    Layout(LayoutError),
+    UnknownTheme(String),
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::Font(e) => write!(f, "{}", e),
+            SettingsError::Layout(e) => write!(f, "{}", e),
+            SettingsError::UnknownTheme(name) => write!(f, "unknown theme or color: {}", name),
+            #[cfg(feature = "toml")]
+            SettingsError::Toml(e) => write!(f, "invalid settings: {}", e),
+            #[cfg(feature = "json")]
+            SettingsError::Json(e) => write!(f, "invalid settings: {}", e),
+        }
+    }
+}
+
+impl Error for SettingsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SettingsError::Font(e) => Some(e),
+            SettingsError::Layout(e) => Some(e),
+            #[cfg(feature = "toml")]
+            SettingsError::Toml(e) => Some(e),
+            #[cfg(feature = "json")]
+            SettingsError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<FontError> for SettingsError {
+    fn from(e: FontError) -> Self {
+        SettingsError::Font(e)
+    }
+}
+
+impl From<LayoutError> for SettingsError {
+    fn from(e: LayoutError) -> Self {
+        SettingsError::Layout(e)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for SettingsError {
+    fn from(e: toml::de::Error) -> Self {
+        SettingsError::Toml(e)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for SettingsError {
+    fn from(e: serde_json::Error) -> Self {
+        SettingsError::Json(e)
+    }
+}
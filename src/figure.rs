@@ -0,0 +1,645 @@
+use crate::canvas;
+use crate::font::{self, Scratch};
+use crate::glyph::Glyph;
+use crate::layout::LayoutMode;
+use crate::rules::Rules;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, IndexMut};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Which edges of a FIGure `trim_sides` should trim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sides {
+    pub top: bool,
+    pub bottom: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl Sides {
+    pub const ALL: Sides = Sides {
+        top: true,
+        bottom: true,
+        left: true,
+        right: true,
+    };
+    pub const VERTICAL: Sides = Sides {
+        top: true,
+        bottom: true,
+        left: false,
+        right: false,
+    };
+    pub const HORIZONTAL: Sides = Sides {
+        top: false,
+        bottom: false,
+        left: true,
+        right: true,
+    };
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FIGure {
+    rows: Vec<Vec<char>>,
+    /// The figure as printed, built on first use and dropped on change.
+    text: OnceLock<String>,
+}
+
+// The cached text follows from the rows, so only they take part.
+impl PartialEq for FIGure {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows == other.rows
+    }
+}
+
+impl Eq for FIGure {}
+
+impl Hash for FIGure {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rows.hash(state);
+    }
+}
+
+impl FIGure {
+    pub fn new(rows: Vec<Vec<char>>) -> Self {
+        FIGure {
+            rows,
+            text: OnceLock::new(),
+        }
+    }
+
+    pub fn rows(&self) -> &[Vec<char>] {
+        &self.rows
+    }
+
+    /// The rows for changing, which drops the cached text.
+    fn rows_mut(&mut self) -> &mut Vec<Vec<char>> {
+        self.text.take();
+        &mut self.rows
+    }
+
+    /// The rows joined with newlines, as `Display` prints them. Computed
+    /// once until the figure changes.
+    pub fn as_str(&self) -> &str {
+        self.text.get_or_init(|| {
+            let rows: Vec<String> = self.rows.iter().map(|row| row.iter().collect()).collect();
+            rows.join("\n")
+        })
+    }
+
+    /// The rows as text, top to bottom.
+    pub fn iter(&self) -> Rows<'_> {
+        Rows {
+            text: self.as_str(),
+            rows: self.rows.iter(),
+        }
+    }
+
+    /// The cell at `row` and `col`, or `None` past the end of either.
+    pub fn get(&self, row: usize, col: usize) -> Option<char> {
+        self.rows.get(row).and_then(|cells| cells.get(col)).cloned()
+    }
+
+    /// The rows joined with `separator` instead of a newline.
+    pub fn join(&self, separator: &str) -> String {
+        self.iter().collect::<Vec<_>>().join(separator)
+    }
+
+    /// Every cell as (row, column, char), row by row.
+    pub fn iter_cells(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, &c)| (y, x, c)))
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Length of the widest row.
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    /// Pad every row with spaces up to the widest row, so the art is a true
+    /// rectangle.
+    pub fn pad_to_rect(&mut self) {
+        let width = self.width();
+        for row in self.rows_mut().iter_mut() {
+            row.resize(width, ' ');
+        }
+    }
+
+    /// Draw `other` over this figure with its top left corner at (x, y).
+    /// Spaces in `other` are transparent, and the figure grows to fit it.
+    pub fn overlay(&mut self, other: &FIGure, x: usize, y: usize) {
+        self.grow(x + other.width(), y + other.height());
+        canvas::blit(self.rows_mut(), other, x, y, None);
+    }
+
+    /// Like `overlay`, but strokes landing on strokes are smushed with
+    /// `rules`, as in `Canvas::blit_smushed`.
+    pub fn overlay_smushed(
+        &mut self,
+        other: &FIGure,
+        x: usize,
+        y: usize,
+        rules: &Rules,
+        hardblank: char,
+    ) {
+        self.grow(x + other.width(), y + other.height());
+        canvas::blit(self.rows_mut(), other, x, y, Some((rules, hardblank)));
+    }
+
+    /// `below` joined under this figure, moved up as far as the vertical
+    /// layout of `rules` allows, e.g. for a subtitle in another font under
+    /// a title. Rendered figures have their hardblanks blanked already, so
+    /// none are looked for.
+    pub fn stack(&self, below: &FIGure, rules: &Rules) -> FIGure {
+        FIGure::new(stack(&self.rows, &below.rows, rules, '\0'))
+    }
+
+    /// `right` joined onto the end of this figure, moved in as far as
+    /// `rules` allow, just as glyphs are placed on a line. Pieces rendered
+    /// with `Font::render_raw` keep the hardblanks that stop the overlap;
+    /// blank them afterwards. The shorter piece is padded at the bottom.
+    pub fn append(&self, right: &FIGure, rules: &Rules, hardblank: char) -> FIGure {
+        let height = self.height().max(right.height());
+        let mut left = self.clone();
+        left.grow(0, height);
+        let mut new = right.clone();
+        new.grow(0, height);
+        let glyph = Glyph::new(0, new.rows);
+        let mut rows = left.rows;
+        let mut trailing: Vec<usize> = rows
+            .iter()
+            .map(|row| row.iter().rev().take_while(|c| **c == ' ').count())
+            .collect();
+        let touches = |c1, c2| font::touches(rules, c1, c2, hardblank);
+        let merge = |c1, c2| font::merge(rules, c1, c2, hardblank, false);
+        let overlay = font::margin_overlay(rules, &rows, &trailing, &glyph, &touches);
+        let line = rows.iter().map(Vec::len).min().unwrap_or(0);
+        font::place(&mut rows, &mut trailing, &glyph, overlay.min(line), &merge);
+        FIGure::new(rows)
+    }
+
+    fn grow(&mut self, width: usize, height: usize) {
+        let width = width.max(self.width());
+        let rows = self.rows_mut();
+        if rows.len() < height {
+            rows.resize(height, vec![]);
+        }
+        for row in rows.iter_mut() {
+            row.resize(width, ' ');
+        }
+    }
+
+    /// Repeat every cell `sx` times across and every row `sy` times down.
+    pub fn scale(&mut self, sx: usize, sy: usize) {
+        let rows = self
+            .rows
+            .iter()
+            .flat_map(|row| {
+                let wide: Vec<char> = row
+                    .iter()
+                    .flat_map(|&c| std::iter::repeat_n(c, sx))
+                    .collect();
+                std::iter::repeat_n(wide, sy)
+            })
+            .collect();
+        *self.rows_mut() = rows;
+    }
+
+    /// Replace every `from` cell with `to`.
+    pub fn replace(&mut self, from: char, to: char) {
+        for c in self.rows_mut().iter_mut().flat_map(|row| row.iter_mut()) {
+            if *c == from {
+                *c = to;
+            }
+        }
+    }
+
+    /// Replace every cell with `f` applied to it.
+    pub fn map_cells<F: Fn(char) -> char>(&mut self, f: F) {
+        for c in self.rows_mut().iter_mut().flat_map(|row| row.iter_mut()) {
+            *c = f(*c);
+        }
+    }
+
+    /// The tight rectangle around all non-space cells, or `None` if the
+    /// figure has no ink at all.
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for (y, row) in self.rows.iter().enumerate() {
+            let left = match row.iter().position(|c| *c != ' ') {
+                Some(left) => left,
+                None => continue,
+            };
+            let right = row.iter().rposition(|c| *c != ' ').unwrap();
+            bounds = Some(match bounds {
+                None => (left, y, right, y),
+                Some((x0, y0, x1, _)) => (x0.min(left), y0, x1.max(right), y),
+            });
+        }
+        bounds.map(|(x0, y0, x1, y1)| Rect {
+            x: x0,
+            y: y0,
+            width: x1 - x0 + 1,
+            height: y1 - y0 + 1,
+        })
+    }
+
+    /// Cut the figure down to its bounding box.
+    pub fn crop_to_content(&mut self) {
+        let bbox = match self.bounding_box() {
+            Some(bbox) => bbox,
+            None => {
+                self.rows_mut().clear();
+                return;
+            }
+        };
+        let rows = self.rows_mut();
+        *rows = rows
+            .drain(bbox.y..bbox.y + bbox.height)
+            .map(|row| {
+                (bbox.x..bbox.x + bbox.width)
+                    .map(|x| row.get(x).cloned().unwrap_or(' '))
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// Remove blank rows from the top and bottom and blank columns from the
+    /// left and right.
+    pub fn trim(&mut self) {
+        self.trim_sides(Sides::ALL);
+    }
+
+    pub fn trim_sides(&mut self, sides: Sides) {
+        let bbox = match self.bounding_box() {
+            Some(bbox) => bbox,
+            None => Rect {
+                x: self.width(),
+                y: self.height(),
+                width: 0,
+                height: 0,
+            },
+        };
+        let rows = self.rows_mut();
+        if sides.bottom {
+            rows.truncate(bbox.y + bbox.height);
+        }
+        if sides.top {
+            rows.drain(..bbox.y.min(rows.len()));
+        }
+        for row in rows.iter_mut() {
+            if sides.right {
+                row.truncate(bbox.x + bbox.width);
+            }
+            if sides.left {
+                row.drain(..bbox.x.min(row.len()));
+            }
+        }
+    }
+}
+
+impl fmt::Display for FIGure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The cell at (row, column). Panics outside the figure; short rows end
+/// early, so pad them with `pad_to_rect` first where that matters.
+impl Index<(usize, usize)> for FIGure {
+    type Output = char;
+
+    fn index(&self, (row, col): (usize, usize)) -> &char {
+        &self.rows[row][col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for FIGure {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut char {
+        &mut self.rows_mut()[row][col]
+    }
+}
+
+/// Iterator over the rows of a `FIGure` as text. Each row is cut from the
+/// printed text by its own length, so a cell holding a newline stays in
+/// its row.
+#[derive(Debug, Clone)]
+pub struct Rows<'a> {
+    text: &'a str,
+    rows: std::slice::Iter<'a, Vec<char>>,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let row = self.rows.next()?;
+        let len = row.iter().copied().map(char::len_utf8).sum();
+        let (row, rest) = self.text.split_at(len);
+        // Skip the newline after the row.
+        self.text = rest.get(1..).unwrap_or("");
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Rows<'_> {}
+
+impl<'a> IntoIterator for &'a FIGure {
+    type Item = &'a str;
+    type IntoIter = Rows<'a>;
+
+    fn into_iter(self) -> Rows<'a> {
+        self.iter()
+    }
+}
+
+/// `below` joined under `above`, moved up as far as the vertical layout of
+/// `rules` allows: fitting closes blank rows between the two, and smushing
+/// goes one row further where every stroke meeting there smushes.
+pub(crate) fn stack(
+    above: &[Vec<char>],
+    below: &[Vec<char>],
+    rules: &Rules,
+    hardblank: char,
+) -> Vec<Vec<char>> {
+    let width = above.iter().chain(below).map(Vec::len).max().unwrap_or(0);
+    let cell = |rows: &[Vec<char>], y: usize, x: usize| rows[y].get(x).copied().unwrap_or(' ');
+    let mut overlay = match rules.vertical_layout {
+        LayoutMode::FullWidth => 0,
+        _ => above.len().min(below.len()),
+    };
+    for x in 0..width {
+        if overlay == 0 {
+            break;
+        }
+        let blank1 = (0..above.len())
+            .rev()
+            .take_while(|&y| cell(above, y, x) == ' ')
+            .count();
+        let blank2 = (0..below.len())
+            .take_while(|&y| cell(below, y, x) == ' ')
+            .count();
+        let mut fits = blank1 + blank2;
+        if blank1 < above.len()
+            && blank2 < below.len()
+            && rules.vertical_layout != LayoutMode::Fitting
+        {
+            let c1 = cell(above, above.len() - 1 - blank1, x);
+            if rules
+                .smush_vertical(c1, cell(below, blank2, x), hardblank)
+                .is_some()
+            {
+                fits += 1;
+            }
+        }
+        overlay = overlay.min(fits);
+    }
+
+    let mut rows = above[..above.len() - overlay].to_vec();
+    for (y, lower) in below.iter().enumerate() {
+        if y >= overlay {
+            rows.push(lower.clone());
+            continue;
+        }
+        let upper = &above[above.len() - overlay + y];
+        let merged = (0..upper.len().max(lower.len()))
+            .map(|x| {
+                let (c1, c2) = (cell(above, above.len() - overlay + y, x), cell(below, y, x));
+                rules
+                    .smush_vertical(c1, c2, hardblank)
+                    .unwrap_or(if c2 == ' ' { c1 } else { c2 })
+            })
+            .collect();
+        rows.push(merged);
+    }
+    rows
+}
+
+/// Reusable storage for `Font::render_into`, holding both the grid of
+/// cells and the text it prints as.
+#[derive(Debug, Default)]
+pub struct FIGureBuf {
+    pub(crate) rows: Vec<Vec<char>>,
+    pub(crate) text: String,
+    pub(crate) scratch: Scratch,
+}
+
+impl FIGureBuf {
+    pub fn new() -> Self {
+        FIGureBuf::default()
+    }
+
+    pub fn rows(&self) -> &[Vec<char>] {
+        &self.rows
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for FIGureBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[test]
+fn pad_to_rect() {
+    let mut fig = FIGure::new(vec![vec!['a'], vec!['b', 'c', 'd'], vec![]]);
+    assert_eq!(fig.width(), 3);
+    fig.pad_to_rect();
+    assert_eq!(fig.to_string(), "a  \nbcd\n   ");
+}
+
+#[test]
+fn bounding_box() {
+    let mut fig = FIGure::new(vec![
+        "      ".chars().collect(),
+        "  /\\  ".chars().collect(),
+        " /  \\".chars().collect(),
+        "    ".chars().collect(),
+    ]);
+    assert_eq!(
+        fig.bounding_box(),
+        Some(Rect {
+            x: 1,
+            y: 1,
+            width: 4,
+            height: 2
+        })
+    );
+    fig.crop_to_content();
+    assert_eq!(fig.to_string(), " /\\ \n/  \\");
+
+    let mut blank = FIGure::new(vec![vec![' '; 3]; 2]);
+    assert_eq!(blank.bounding_box(), None);
+    blank.crop_to_content();
+    assert_eq!(blank.height(), 0);
+}
+
+#[test]
+fn trim() {
+    let art = || {
+        FIGure::new(vec![
+            "     ".chars().collect(),
+            "  _  ".chars().collect(),
+            " |_| ".chars().collect(),
+            "".chars().collect(),
+        ])
+    };
+    let mut fig = art();
+    fig.trim();
+    assert_eq!(fig.to_string(), " _ \n|_|");
+
+    let mut fig = art();
+    fig.trim_sides(Sides::VERTICAL);
+    assert_eq!(fig.to_string(), "  _  \n |_| ");
+
+    let mut fig = art();
+    fig.trim_sides(Sides {
+        top: true,
+        bottom: false,
+        left: false,
+        right: true,
+    });
+    assert_eq!(fig.to_string(), "  _ \n |_|\n");
+
+    let mut blank = FIGure::new(vec![vec![' '; 2]; 2]);
+    blank.trim();
+    assert_eq!(blank.height(), 0);
+}
+
+#[test]
+fn overlay() {
+    let mut fig = FIGure::new(vec!["/\\".chars().collect(), "\\/".chars().collect()]);
+    fig.overlay(&FIGure::new(vec![vec!['*', ' ', '*']]), 1, 1);
+    assert_eq!(fig.to_string(), "/\\  \n\\* *");
+    fig.overlay(&FIGure::new(vec![vec!['.']]), 3, 2);
+    assert_eq!(fig.to_string(), "/\\  \n\\* *\n   .");
+
+    use crate::layout::{LayoutMode, SmushingRule};
+    let rules = Rules {
+        horizontal_layout: LayoutMode::ControlledSmush,
+        vertical_layout: LayoutMode::FullWidth,
+        horizontal_rules: vec![SmushingRule::HorizontalBigX],
+        vertical_rules: vec![],
+    };
+    let mut fig = FIGure::new(vec![vec!['/']]);
+    fig.overlay_smushed(&FIGure::new(vec![vec!['\\']]), 0, 0, &rules, '$');
+    assert_eq!(fig.to_string(), "|");
+}
+
+#[test]
+fn scale() {
+    let mut fig = FIGure::new(vec!["/\\".chars().collect(), "|".chars().collect()]);
+    fig.scale(2, 2);
+    assert_eq!(fig.to_string(), "//\\\\\n//\\\\\n||\n||");
+    fig.scale(1, 0);
+    assert_eq!(fig.height(), 0);
+}
+
+#[test]
+fn iter_rows() {
+    let mut fig = FIGure::new(vec!["/\\".chars().collect(), "|".chars().collect()]);
+    assert_eq!(fig.iter().collect::<Vec<_>>(), vec!["/\\", "|"]);
+    assert_eq!(
+        fig.iter_cells().collect::<Vec<_>>(),
+        vec![(0, 0, '/'), (0, 1, '\\'), (1, 0, '|')]
+    );
+    fig.pad_to_rect();
+    let rows: Vec<&str> = (&fig).into_iter().collect();
+    assert_eq!(rows, vec!["/\\", "| "]);
+    assert_eq!(FIGure::default().iter().count(), 0);
+    assert_eq!(fig.join("\r\n"), "/\\\r\n| ");
+    assert_eq!(
+        FIGure::new(vec![vec![]]).iter().collect::<Vec<_>>(),
+        vec![""]
+    );
+    // A newline in a cell doesn't start a new row.
+    fig[(0, 0)] = '\n';
+    assert_eq!(fig.iter().len(), fig.height());
+    assert_eq!(fig.iter().collect::<Vec<_>>(), vec!["\n\\", "| "]);
+}
+
+#[test]
+fn index_cells() {
+    let mut fig = FIGure::new(vec!["/\\".chars().collect(), "|".chars().collect()]);
+    assert_eq!(fig.get(0, 1), Some('\\'));
+    assert_eq!(fig.get(1, 1), None);
+    assert_eq!(fig.get(2, 0), None);
+    assert_eq!(fig[(1, 0)], '|');
+    assert_eq!(fig.to_string(), "/\\\n|");
+    fig[(1, 0)] = '#';
+    assert_eq!(fig.to_string(), "/\\\n#");
+}
+
+#[test]
+fn stack_rows() {
+    let rows = |lines: &[&str]| -> Vec<Vec<char>> {
+        lines.iter().map(|line| line.chars().collect()).collect()
+    };
+    let above = rows(&[" _ ", "|_|", "   "]);
+    let below = rows(&["   ", " - ", "| |"]);
+    let mut rules = Rules::default();
+    assert_eq!(stack(&above, &below, &rules, '$').len(), 6);
+    rules.vertical_layout = LayoutMode::Fitting;
+    assert_eq!(
+        stack(&above, &below, &rules, '$'),
+        rows(&[" _ ", "|_|", " - ", "| |"])
+    );
+    rules = "smush hline".parse().unwrap();
+    assert_eq!(
+        stack(&above, &below, &rules, '$'),
+        rows(&[" _ ", "|=|", "| |"])
+    );
+}
+
+#[test]
+fn stack_figures() {
+    let f = crate::font::Font::load_font("Standard.flf").unwrap();
+    let (title, subtitle) = (f.render("Hi"), f.render("there"));
+    let rules: Rules = "smush hline".parse().unwrap();
+    let stacked = title.stack(&subtitle, &rules);
+    assert_eq!(
+        stacked.rows(),
+        stack(title.rows(), subtitle.rows(), &rules, '$')
+    );
+    assert!(stacked.height() < title.height() + subtitle.height());
+    let full = title.stack(&subtitle, &Rules::default());
+    assert_eq!(full.to_string(), format!("{}\n{}", title, subtitle));
+}
+
+#[test]
+fn append_figures() {
+    let f = crate::font::Font::load_font("Standard.flf").unwrap();
+    let hardblank = f.font_head.hardblank();
+    let mut joined = f
+        .render_raw("Hi")
+        .append(&f.render_raw(" there"), f.layout(), hardblank);
+    joined.replace(hardblank, ' ');
+    assert_eq!(joined, f.render("Hi there"));
+
+    let full = FIGure::new(vec![vec!['a', ' '], vec!['b']]);
+    let tall = FIGure::new(vec![vec![' ', 'c'], vec!['d'], vec!['e']]);
+    let appended = full.append(&tall, &"fitting".parse().unwrap(), '$');
+    assert_eq!(appended.to_string(), "a c\nbd \n e ");
+}
@@ -0,0 +1,538 @@
+use crate::canvas::Canvas;
+use crate::figure::FIGure;
+use crate::font::Font;
+use std::fmt;
+
+/// A post-processing step applied to a rendered FIGure.
+pub trait Filter: fmt::Debug {
+    fn apply(&self, figure: FIGure) -> FIGure;
+}
+
+/// Draws a copy of the strokes offset by (dx, dy) behind the original.
+#[derive(Debug, Clone)]
+pub struct Shadow {
+    pub dx: isize,
+    pub dy: isize,
+    pub fill: char,
+}
+
+impl Shadow {
+    pub fn new(dx: isize, dy: isize, fill: char) -> Self {
+        Shadow { dx, dy, fill }
+    }
+}
+
+impl Filter for Shadow {
+    fn apply(&self, figure: FIGure) -> FIGure {
+        let shadow = FIGure::new(
+            figure
+                .rows()
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&c| if c == ' ' { ' ' } else { self.fill })
+                        .collect()
+                })
+                .collect(),
+        );
+        let (sx, ox) = split_offset(self.dx);
+        let (sy, oy) = split_offset(self.dy);
+        let mut canvas = Canvas::new(
+            figure.width() + self.dx.unsigned_abs(),
+            figure.height() + self.dy.unsigned_abs(),
+        );
+        canvas.blit(&shadow, sx, sy);
+        canvas.blit(&figure, ox, oy);
+        canvas.into_figure()
+    }
+}
+
+/// Where the shadow and the original go for an offset of `d`.
+fn split_offset(d: isize) -> (usize, usize) {
+    if d >= 0 {
+        (d as usize, 0)
+    } else {
+        (0, d.unsigned_abs())
+    }
+}
+
+/// Thickens strokes by double-striking every cell one column to the right.
+/// A copy never overwrites an existing stroke, so `/\` stays a peak and
+/// `|` only widens into empty space.
+#[derive(Debug, Clone, Default)]
+pub struct Bold;
+
+impl Filter for Bold {
+    fn apply(&self, figure: FIGure) -> FIGure {
+        let width = figure.width() + 1;
+        FIGure::new(
+            figure
+                .rows()
+                .iter()
+                .map(|row| {
+                    let mut bold = row.clone();
+                    bold.resize(width, ' ');
+                    for (x, &c) in row.iter().enumerate() {
+                        if c != ' ' && bold[x + 1] == ' ' {
+                            bold[x + 1] = c;
+                        }
+                    }
+                    bold
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinePosition {
+    /// On a new row under the banner.
+    Below,
+    /// Through an existing row, behind the strokes.
+    Row(usize),
+}
+
+/// Draws a horizontal line across the whole banner, for underlines and
+/// strikethroughs.
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub fill: char,
+    pub position: LinePosition,
+}
+
+impl Line {
+    pub fn underline(fill: char) -> Self {
+        Line {
+            fill,
+            position: LinePosition::Below,
+        }
+    }
+
+    /// A line through the bottom row of `font`'s letters.
+    pub fn baseline(font: &Font, fill: char) -> Self {
+        Line {
+            fill,
+            position: LinePosition::Row(font.font_head.baseline().saturating_sub(1)),
+        }
+    }
+
+    /// A line through the middle of `font`'s letters.
+    pub fn strikethrough(font: &Font, fill: char) -> Self {
+        Line {
+            fill,
+            position: LinePosition::Row(font.font_head.baseline() / 2),
+        }
+    }
+}
+
+impl Filter for Line {
+    fn apply(&self, figure: FIGure) -> FIGure {
+        let width = figure.width();
+        let mut rows = figure.rows().to_vec();
+        match self.position {
+            LinePosition::Below => rows.push(vec![self.fill; width]),
+            LinePosition::Row(y) => {
+                if let Some(row) = rows.get_mut(y) {
+                    row.resize(width, ' ');
+                    for c in row.iter_mut().filter(|c| **c == ' ') {
+                        *c = self.fill;
+                    }
+                }
+            }
+        }
+        FIGure::new(rows)
+    }
+}
+
+/// How a `Pattern` picks the character for each stroke cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternMode {
+    /// Cycle through the pattern one stroke cell at a time, row by row.
+    Sequence,
+    /// Pick by column, so every stroke in a column gets the same character.
+    Column,
+}
+
+/// Replaces every stroke character with a repeating pattern, e.g. `▒▓█`.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub chars: Vec<char>,
+    pub mode: PatternMode,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str, mode: PatternMode) -> Self {
+        Pattern {
+            chars: pattern.chars().collect(),
+            mode,
+        }
+    }
+}
+
+impl Filter for Pattern {
+    fn apply(&self, figure: FIGure) -> FIGure {
+        if self.chars.is_empty() {
+            return figure;
+        }
+        let mut n = 0;
+        FIGure::new(
+            figure
+                .rows()
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .enumerate()
+                        .map(|(x, &c)| {
+                            if c == ' ' {
+                                return c;
+                            }
+                            let i = match self.mode {
+                                PatternMode::Sequence => {
+                                    n += 1;
+                                    n - 1
+                                }
+                                PatternMode::Column => x,
+                            };
+                            self.chars[i % self.chars.len()]
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Blows the banner up by integer factors; see `FIGure::scale`.
+#[derive(Debug, Clone)]
+pub struct Scale {
+    pub sx: usize,
+    pub sy: usize,
+}
+
+impl Scale {
+    pub fn new(sx: usize, sy: usize) -> Self {
+        Scale { sx, sy }
+    }
+}
+
+impl Filter for Scale {
+    fn apply(&self, mut figure: FIGure) -> FIGure {
+        figure.scale(self.sx, self.sy);
+        figure
+    }
+}
+
+/// Halves the banner's height by merging each pair of rows into one with
+/// the half-block characters `▀`, `▄` and `█`. All strokes become blocks.
+#[derive(Debug, Clone, Default)]
+pub struct HalfHeight;
+
+impl Filter for HalfHeight {
+    fn apply(&self, figure: FIGure) -> FIGure {
+        let ink = |row: Option<&Vec<char>>, x: usize| {
+            row.and_then(|row| row.get(x)).is_some_and(|c| *c != ' ')
+        };
+        FIGure::new(
+            figure
+                .rows()
+                .chunks(2)
+                .map(|pair| {
+                    let width = pair.iter().map(|row| row.len()).max().unwrap_or(0);
+                    (0..width)
+                        .map(|x| match (ink(pair.first(), x), ink(pair.get(1), x)) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Fakes depth by extruding every stroke `depth` cells diagonally behind
+/// the original, down and to the right, or up and to the right when
+/// `rising`. The extrusion is drawn with `\` going down and `/` going up,
+/// unless `fill` says otherwise.
+#[derive(Debug, Clone)]
+pub struct Extrude {
+    pub depth: usize,
+    pub rising: bool,
+    pub fill: Option<char>,
+}
+
+impl Extrude {
+    pub fn new(depth: usize) -> Self {
+        Extrude {
+            depth,
+            rising: false,
+            fill: None,
+        }
+    }
+
+    pub fn rising(mut self, rising: bool) -> Self {
+        self.rising = rising;
+        self
+    }
+
+    pub fn fill(mut self, fill: char) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+}
+
+impl Filter for Extrude {
+    fn apply(&self, figure: FIGure) -> FIGure {
+        let fill = match (self.fill, self.rising) {
+            (Some(fill), _) => fill,
+            (None, false) => '\\',
+            (None, true) => '/',
+        };
+        let mut side = figure.clone();
+        side.map_cells(|c| if c == ' ' { ' ' } else { fill });
+        let mut canvas = Canvas::new(figure.width() + self.depth, figure.height() + self.depth);
+        // The furthest layer goes down first so nearer ones cover it.
+        let top = if self.rising { self.depth } else { 0 };
+        for k in (1..=self.depth).rev() {
+            let y = if self.rising { top - k } else { k };
+            canvas.blit(&side, k, y);
+        }
+        canvas.blit(&figure, 0, top);
+        canvas.into_figure()
+    }
+}
+
+/// Hollows out solid fonts by keeping only strokes that touch a space
+/// above, below, left or right. The edge of the banner counts as space.
+#[derive(Debug, Clone, Default)]
+pub struct Outline;
+
+impl Filter for Outline {
+    fn apply(&self, figure: FIGure) -> FIGure {
+        let rows = figure.rows();
+        let blank = |x: Option<usize>, y: Option<usize>| match (x, y.and_then(|y| rows.get(y))) {
+            (Some(x), Some(row)) => row.get(x).is_none_or(|c| *c == ' '),
+            _ => true,
+        };
+        FIGure::new(
+            rows.iter()
+                .enumerate()
+                .map(|(y, row)| {
+                    row.iter()
+                        .enumerate()
+                        .map(|(x, &c)| {
+                            let edge = blank(x.checked_sub(1), Some(y))
+                                || blank(Some(x + 1), Some(y))
+                                || blank(Some(x), y.checked_sub(1))
+                                || blank(Some(x), Some(y + 1));
+                            if edge {
+                                c
+                            } else {
+                                ' '
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Stencil effect: within the banner's rectangle spaces become `fill` and
+/// strokes become spaces.
+#[derive(Debug, Clone)]
+pub struct Inverse {
+    pub fill: char,
+}
+
+impl Inverse {
+    pub fn new(fill: char) -> Self {
+        Inverse { fill }
+    }
+}
+
+impl Filter for Inverse {
+    fn apply(&self, mut figure: FIGure) -> FIGure {
+        figure.pad_to_rect();
+        FIGure::new(
+            figure
+                .rows()
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&c| if c == ' ' { self.fill } else { ' ' })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BubbleKind {
+    /// Sharp corners and a `\` or `/` tail.
+    #[default]
+    Speech,
+    /// Round sides and a trail of `o`s.
+    Thought,
+}
+
+/// Which end of the bubble the tail hangs from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TailSide {
+    #[default]
+    Left,
+    Right,
+}
+
+/// Wraps the banner in a cowsay-style speech or thought bubble, with a
+/// two-row tail underneath.
+#[derive(Debug, Clone, Default)]
+pub struct Bubble {
+    pub kind: BubbleKind,
+    pub tail: TailSide,
+}
+
+impl Bubble {
+    pub fn new(kind: BubbleKind, tail: TailSide) -> Self {
+        Bubble { kind, tail }
+    }
+}
+
+impl Filter for Bubble {
+    fn apply(&self, mut figure: FIGure) -> FIGure {
+        figure.pad_to_rect();
+        let width = figure.width();
+        let height = figure.height();
+        let line = |fill: char| {
+            let mut row = vec![' '];
+            row.extend(std::iter::repeat_n(fill, width + 2));
+            row
+        };
+        let mut rows = vec![line('_')];
+        for (y, row) in figure.rows().iter().enumerate() {
+            let (left, right) = match self.kind {
+                BubbleKind::Thought => ('(', ')'),
+                BubbleKind::Speech if height == 1 => ('<', '>'),
+                BubbleKind::Speech if y == 0 => ('/', '\\'),
+                BubbleKind::Speech if y + 1 == height => ('\\', '/'),
+                BubbleKind::Speech => ('|', '|'),
+            };
+            let mut framed = vec![left, ' '];
+            framed.extend_from_slice(row);
+            framed.extend_from_slice(&[' ', right]);
+            rows.push(framed);
+        }
+        rows.push(line('-'));
+        for step in 0..2 {
+            let (x, c) = match self.tail {
+                TailSide::Left => (4 + step, '\\'),
+                TailSide::Right => ((width + 3).saturating_sub(4 + step), '/'),
+            };
+            let c = if self.kind == BubbleKind::Thought {
+                'o'
+            } else {
+                c
+            };
+            let mut row = vec![' '; x];
+            row.push(c);
+            rows.push(row);
+        }
+        FIGure::new(rows)
+    }
+}
+
+#[cfg(test)]
+fn figure(rows: &[&str]) -> FIGure {
+    FIGure::new(rows.iter().map(|row| row.chars().collect()).collect())
+}
+
+#[test]
+fn shadow() {
+    let shadowed = Shadow::new(1, 1, '.').apply(figure(&["/\\", "\\/"]));
+    assert_eq!(shadowed.to_string(), "/\\ \n\\/.\n ..");
+    let shadowed = Shadow::new(-1, 0, '#').apply(figure(&["ab"]));
+    assert_eq!(shadowed.to_string(), "#ab");
+}
+
+#[test]
+fn extrude() {
+    let deep = Extrude::new(2).apply(figure(&["##", "#"]));
+    assert_eq!(deep.to_string(), "##  \n#\\\\ \n \\\\\\\n  \\ ");
+    let rising = Extrude::new(1).rising(true).fill('.').apply(figure(&["#"]));
+    assert_eq!(rising.to_string(), " .\n# ");
+}
+
+#[test]
+fn bold() {
+    let bold = Bold.apply(figure(&[" /\\ ", "| _ |"]));
+    assert_eq!(bold.to_string(), " /\\\\  \n||__||");
+}
+
+#[test]
+fn line() {
+    let art = || figure(&[" _ ", "|_|", "| |"]);
+    assert_eq!(
+        Line::underline('=').apply(art()).to_string(),
+        " _ \n|_|\n| |\n==="
+    );
+    let strike = Line {
+        fill: '-',
+        position: LinePosition::Row(1),
+    };
+    assert_eq!(strike.apply(art()).to_string(), " _ \n|_|\n| |");
+    let strike = Line {
+        fill: '-',
+        position: LinePosition::Row(2),
+    };
+    assert_eq!(strike.apply(art()).to_string(), " _ \n|_|\n|-|");
+
+    let f = Font::load_font("Standard.flf").unwrap();
+    assert_eq!(Line::baseline(&f, '_').position, LinePosition::Row(4));
+    assert_eq!(Line::strikethrough(&f, '-').position, LinePosition::Row(2));
+}
+
+#[test]
+fn pattern() {
+    let art = || figure(&["/\\ |", " ||"]);
+    let seq = Pattern::new("ab", PatternMode::Sequence).apply(art());
+    assert_eq!(seq.to_string(), "ab a\n ba");
+    let col = Pattern::new("123", PatternMode::Column).apply(art());
+    assert_eq!(col.to_string(), "12 1\n 23");
+}
+
+#[test]
+fn half_height() {
+    let half = HalfHeight.apply(figure(&["## #", "#  #", " #"]));
+    assert_eq!(half.to_string(), "█▀ █\n ▀");
+}
+
+#[test]
+fn outline() {
+    let hollow = Outline.apply(figure(&["####", "####", "####", "## "]));
+    assert_eq!(hollow.to_string(), "####\n#  #\n# ##\n## ");
+}
+
+#[test]
+fn inverse() {
+    let stencil = Inverse::new('#').apply(figure(&[" /\\", "/"]));
+    assert_eq!(stencil.to_string(), "#  \n ##");
+}
+
+#[test]
+fn bubble() {
+    let speech = Bubble::default().apply(figure(&["/\\", "\\/"]));
+    assert_eq!(
+        speech.to_string(),
+        " ____\n/ /\\ \\\n\\ \\/ /\n ----\n    \\\n     \\"
+    );
+    let thought = Bubble::new(BubbleKind::Thought, TailSide::Right).apply(figure(&["hi"]));
+    assert_eq!(thought.to_string(), " ____\n( hi )\n ----\n o\no");
+    let single = Bubble::default().apply(figure(&["a", "bc", "d"]));
+    assert_eq!(single.rows()[1], "/ a  \\".chars().collect::<Vec<_>>());
+    assert_eq!(single.rows()[2], "| bc |".chars().collect::<Vec<_>>());
+    let one = Bubble::default().apply(figure(&["x"]));
+    assert_eq!(one.rows()[1], "< x >".chars().collect::<Vec<_>>());
+}
@@ -1,35 +1,48 @@
+use crate::debug::{self, Junction};
+use crate::error::{FontError, FontLimit};
+use crate::figure::{FIGure, FIGureBuf};
+use crate::glyph::Glyph;
 use crate::layout::*;
+use crate::render::{CancelToken, MissingPolicy};
 use crate::rules::*;
-use std::collections::HashMap;
+use crate::shaping;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
-use strum::IntoEnumIterator;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct FontOpts {
-    hardblank: char,
-    height: usize,
+    pub(crate) hardblank: char,
+    pub(crate) height: usize,
     baseline: usize,
-    max_length: usize,
+    pub(crate) max_length: usize,
     old_layout: isize,
-    comment_lines: usize,
+    pub(crate) comment_lines: usize,
     print_direction: usize,
-    full_layout: Option<isize>,
+    full_layout: Option<LayoutFlags>,
     codetag_count: Option<usize>,
 }
 
 impl FontOpts {
     pub fn parse(line: &str) -> Result<FontOpts, std::num::ParseIntError> {
         let mut head = line.split_ascii_whitespace();
-        let signature = head.next().unwrap();
-        let height: usize = head.next().unwrap().parse()?;
-        let baseline: usize = head.next().unwrap().parse()?;
-        let max_length: usize = head.next().unwrap().parse()?;
-        let old_layout: isize = head.next().unwrap().parse()?;
-        let comment_lines: usize = head.next().unwrap().parse()?;
+        let signature = head.next().unwrap_or("");
+        let height: usize = head.next().unwrap_or("").parse()?;
+        let baseline: usize = head.next().unwrap_or("").parse()?;
+        let max_length: usize = head.next().unwrap_or("").parse()?;
+        let old_layout: isize = head.next().unwrap_or("").parse()?;
+        let comment_lines: usize = head.next().unwrap_or("").parse()?;
         let print_direction: usize = head.next().unwrap_or("0").parse()?;
-        let full_layout = head.next().and_then(|fl| fl.parse::<isize>().ok());
+        let full_layout = head
+            .next()
+            .and_then(|fl| fl.parse::<u32>().ok())
+            .map(LayoutFlags::from_bits_truncate);
         let codetag_count = head.next().and_then(|cc| cc.parse::<usize>().ok());
 
         Ok(FontOpts {
@@ -44,6 +57,51 @@ impl FontOpts {
             codetag_count,
         })
     }
+
+    pub fn hardblank(&self) -> char {
+        self.hardblank
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn baseline(&self) -> usize {
+        self.baseline
+    }
+
+    pub fn set_baseline(&mut self, baseline: usize) {
+        self.baseline = baseline;
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    pub fn old_layout(&self) -> isize {
+        self.old_layout
+    }
+
+    pub fn full_layout(&self) -> Option<LayoutFlags> {
+        self.full_layout
+    }
+
+    pub fn comment_lines(&self) -> usize {
+        self.comment_lines
+    }
+
+    /// 0 for left-to-right, 1 for right-to-left.
+    pub fn print_direction(&self) -> usize {
+        self.print_direction
+    }
+
+    pub fn set_print_direction(&mut self, print_direction: usize) {
+        self.print_direction = print_direction;
+    }
+
+    pub fn codetag_count(&self) -> Option<usize> {
+        self.codetag_count
+    }
 }
 
 #[test]
@@ -58,153 +116,1012 @@ fn parse_font_head() {
     assert_eq!(fo.print_direction, 0);
     assert_eq!(fo.full_layout, None);
     assert_eq!(fo.codetag_count, None);
+
+    assert!(FontOpts::parse("flf2a$ 8 8").is_err());
+
+    let mut fo = FontOpts::parse("flf2a$ 6 5 16 15 13 0 24463 229").unwrap();
+    assert_eq!(fo.hardblank(), '$');
+    assert_eq!(fo.height(), 6);
+    assert_eq!(fo.baseline(), 5);
+    assert_eq!(fo.max_length(), 16);
+    assert_eq!(fo.old_layout(), 15);
+    assert_eq!(fo.comment_lines(), 13);
+    assert_eq!(fo.full_layout().map(|flags| flags.bits()), Some(24463));
+    assert_eq!(fo.codetag_count(), Some(229));
+    fo.set_print_direction(1);
+    assert_eq!(fo.print_direction(), 1);
+    assert!(FontOpts::parse("").is_err());
+}
+
+/// The seven German characters every font must define after ASCII.
+pub(crate) const DEUTSCH_CODES: [i32; 7] = [196, 214, 220, 228, 246, 252, 223];
+
+fn is_terminator(row: &str, endmark: char) -> bool {
+    let mut chars = row.trim_end().chars().rev();
+    chars.next() == Some(endmark) && chars.next() == Some(endmark)
+}
+
+/// How many of `lines` belong to the glyph starting at `lines[0]`: up to
+/// the first row with a doubled endmark, but never more than `height` rows,
+/// so that a glyph missing its terminator doesn't swallow the next one.
+pub(crate) fn block_len(lines: &[&str], height: usize) -> usize {
+    let endmark = match lines.first().and_then(|l| l.trim_end().chars().last()) {
+        Some(endmark) => endmark,
+        None => return height.min(lines.len()),
+    };
+    if height == 0 {
+        return 0;
+    }
+    // Art ending in the endmark character can look like an early
+    // terminator, so a terminator in the expected place wins.
+    if lines
+        .get(height - 1)
+        .is_some_and(|l| is_terminator(l, endmark))
+    {
+        return height;
+    }
+    match lines
+        .iter()
+        .take(height)
+        .position(|l| is_terminator(l, endmark))
+    {
+        Some(i) => i + 1,
+        None => height.min(lines.len()),
+    }
+}
+
+/// Strip whitespace and the run of endmarks from the end of a row.
+pub(crate) fn strip_row(row: &str) -> &str {
+    let row = row.trim_end();
+    match row.chars().last() {
+        Some(endmark) => row.trim_end_matches(endmark),
+        None => row,
+    }
+}
+
+pub(crate) fn parse_code_tag(line: &str) -> Option<i32> {
+    let tag = line.split_whitespace().next()?;
+    let (negative, digits) = match tag.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, tag),
+    };
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i64::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse::<i64>().ok()?
+    };
+    let value = if negative { -value } else { value };
+    if value < i64::from(i32::MIN) + 1 || value > i64::from(i32::MAX) {
+        return None;
+    }
+    Some(value as i32)
+}
+
+/// Read the glyph whose art starts at `lines[0]`, line number `line` of
+/// the file, returning it along with how many lines it took up.
+fn read_glyph(
+    lines: &[&str],
+    line: usize,
+    height: usize,
+    code: i32,
+    options: &ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<(Glyph, usize), FontError> {
+    let len = block_len(lines, height);
+    if len != height {
+        if options.strict {
+            return Err(FontError::GlyphHeight {
+                code,
+                expected: height,
+                found: len,
+            });
+        }
+        warnings.push(ParseWarning::GlyphHeight {
+            line,
+            code,
+            expected: height,
+            found: len,
+        });
+    }
+    let mut rows: Vec<Vec<char>> = lines[..len]
+        .iter()
+        .map(|row| strip_row(row).chars().collect())
+        .collect();
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    rows.resize(height, vec![' '; width]);
+    Ok((Glyph::new(code, rows), len))
+}
+
+#[test]
+fn code_tags() {
+    assert_eq!(parse_code_tag("196  LATIN CAPITAL"), Some(196));
+    assert_eq!(parse_code_tag("0x05D0"), Some(0x05d0));
+    assert_eq!(parse_code_tag("0177"), Some(127));
+    assert_eq!(parse_code_tag("-2"), Some(-2));
+    assert_eq!(parse_code_tag("-2147483648"), None);
+    assert_eq!(parse_code_tag("abc"), None);
+}
+
+/// A flaw `Font::parse_font_with_warnings` worked around, with the 1-based
+/// line it was found on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// The glyph had the wrong number of rows and was padded with blank
+    /// ones.
+    GlyphHeight {
+        line: usize,
+        code: i32,
+        expected: usize,
+        found: usize,
+    },
+    /// The code tag was unreadable or out of range, so its glyph was
+    /// skipped.
+    BadCodeTag { line: usize },
+    /// The glyph was tagged -1, which names no character, and skipped.
+    ReservedCode { line: usize },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::GlyphHeight {
+                line,
+                code,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: glyph {} has {} rows, expected {}",
+                line, code, found, expected
+            ),
+            ParseWarning::BadCodeTag { line } => {
+                write!(f, "line {}: invalid code tag, glyph skipped", line)
+            }
+            ParseWarning::ReservedCode { line } => {
+                write!(f, "line {}: glyph tagged -1 skipped", line)
+            }
+        }
+    }
+}
+
+/// Caps on what a font may ask the parser for, for loading fonts from
+/// untrusted sources. Nothing is capped by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FontLimits {
+    height: Option<usize>,
+    max_length: Option<usize>,
+    codetags: Option<usize>,
+    glyph_bytes: Option<usize>,
+}
+
+impl FontLimits {
+    pub fn new() -> Self {
+        FontLimits::default()
+    }
+
+    /// The tallest glyphs the header may declare. Short glyphs are padded
+    /// to this height, so it bounds how much a tiny file can expand.
+    pub fn height(mut self, height: usize) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// The largest max_length the header may declare.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// The most code-tagged glyphs, whether declared in the header or
+    /// found in the file.
+    pub fn codetags(mut self, codetags: usize) -> Self {
+        self.codetags = Some(codetags);
+        self
+    }
+
+    /// The most memory, in bytes, the cells of all glyphs may take up.
+    pub fn glyph_bytes(mut self, glyph_bytes: usize) -> Self {
+        self.glyph_bytes = Some(glyph_bytes);
+        self
+    }
+
+    fn check(limit: Option<usize>, what: FontLimit, value: usize) -> Result<(), FontError> {
+        match limit {
+            Some(limit) if value > limit => Err(FontError::TooLarge { what, value, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How strictly `Font::parse_font_with` treats malformed fonts.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    strict: bool,
+    encoding: Encoding,
+    limits: FontLimits,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// In strict mode a glyph whose row count doesn't match the header's
+    /// height is an error; otherwise it's padded with blank rows.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// How to decode font files read as bytes.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Fail with `FontError::TooLarge` on fonts bigger than `limits`.
+    pub fn limits(mut self, limits: FontLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Text encoding of a font file. Many classic fonts are Latin-1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// UTF-8 if the data is valid UTF-8, Latin-1 otherwise.
+    #[default]
+    Auto,
+    Utf8,
+    Latin1,
 }
 
+impl Encoding {
+    pub fn decode(self, data: &[u8]) -> Result<Cow<'_, str>, FontError> {
+        match self {
+            Encoding::Utf8 => Ok(Cow::Borrowed(std::str::from_utf8(data)?)),
+            Encoding::Latin1 => Ok(Cow::Owned(data.iter().map(|&b| char::from(b)).collect())),
+            Encoding::Auto => match std::str::from_utf8(data) {
+                Ok(text) => Ok(Cow::Borrowed(text)),
+                Err(_) => Encoding::Latin1.decode(data),
+            },
+        }
+    }
+}
+
+#[test]
+fn decode() {
+    let latin1 = b"caf\xe9";
+    assert_eq!(Encoding::Auto.decode(latin1).unwrap(), "caf\u{e9}");
+    assert_eq!(Encoding::Latin1.decode(latin1).unwrap(), "caf\u{e9}");
+    assert!(Encoding::Utf8.decode(latin1).is_err());
+    let utf8 = "caf\u{e9}".as_bytes();
+    assert_eq!(Encoding::Auto.decode(utf8).unwrap(), "caf\u{e9}");
+    assert_eq!(Encoding::Latin1.decode(utf8).unwrap(), "caf\u{c3}\u{a9}");
+}
+
+/// A font built into the program, e.g. with `include_bytes!`, that's
+/// parsed the first time it's used and then shared for the rest of the run.
+/// Parsing copies the glyphs into owned rows as `load_font` does, so a font
+/// that's used costs the same heap as a loaded one; what's saved is the
+/// work and memory for fonts that are never used. It can be a `static`:
+/// `static SLANT: StaticFont = StaticFont::new("Slant.flf", include_bytes!("Slant.flf"));`
+#[derive(Debug)]
+pub struct StaticFont {
+    name: &'static str,
+    data: &'static [u8],
+    font: OnceLock<Result<Font, FontError>>,
+}
+
+impl StaticFont {
+    pub const fn new(name: &'static str, data: &'static [u8]) -> Self {
+        StaticFont {
+            name,
+            data,
+            font: OnceLock::new(),
+        }
+    }
+
+    /// The parsed font, or why it couldn't be parsed.
+    pub fn try_get(&'static self) -> Result<&'static Font, &'static FontError> {
+        self.font
+            .get_or_init(|| Font::parse_bytes(self.name, self.data, &ParseOptions::default()))
+            .as_ref()
+    }
+
+    /// The parsed font. Panics if the data isn't a font, which for data
+    /// built into the program is a bug.
+    pub fn get(&'static self) -> &'static Font {
+        match self.try_get() {
+            Ok(font) => font,
+            Err(e) => panic!("built-in font {} is invalid: {}", self.name, e),
+        }
+    }
+}
+
+#[test]
+fn static_fonts() {
+    static SLANT: StaticFont = StaticFont::new("Slant.flf", include_bytes!("../fonts/Slant.flf"));
+    static BROKEN: StaticFont = StaticFont::new("broken.flf", b"flf2a$ x");
+    assert!(std::ptr::eq(SLANT.get(), SLANT.get()));
+    assert_eq!(*SLANT.get(), Font::load_font("Slant.flf").unwrap());
+    assert!(BROKEN.try_get().is_err());
+}
+
+/// Per-render settings for `Font::compose`.
+#[derive(Default)]
+pub(crate) struct Compose<'a> {
+    pub rtl: bool,
+    /// Gap adjustments between pairs of characters, keyed in visual order.
+    pub kerning: Option<&'a HashMap<(char, char), isize>>,
+    /// Sequences drawn with a single glyph code wherever they appear.
+    pub ligatures: &'a [(String, i32)],
+    pub missing: MissingPolicy,
+    /// Layout rules to use instead of the font's own.
+    pub rules: Option<&'a Rules>,
+    /// Stop laying out glyphs, leaving the line short, once cancelled.
+    pub cancel: Option<&'a CancelToken>,
+    /// Stop laying out glyphs once the line is wider than this.
+    pub max_width: Option<usize>,
+}
+
+/// Working storage for `Font::compose_into`, which `FIGureBuf` keeps
+/// between renders so that they reuse it.
 #[derive(Debug, Default)]
+pub(crate) struct Scratch {
+    /// Glyph codes to draw, with the character each one stands for.
+    units: Vec<(i32, Option<char>)>,
+    /// Where in the message each unit's character is, when tracing.
+    offsets: Vec<usize>,
+    /// The overlap of each pair of glyph codes met so far.
+    pairs: HashMap<(i32, i32), usize>,
+    /// How many blank columns end each row.
+    trailing: Vec<usize>,
+}
+
+/// File name extensions `Font::load_font` adds to a bare font name: FIGfonts
+/// and TOIlet fonts.
+pub const FONT_SUFFIXES: [&str; 2] = [".flf", ".tlf"];
+
+/// Added after one of `FONT_SUFFIXES` for a gzipped font, e.g.
+/// "Slant.flf.gz".
+#[cfg(feature = "compressed")]
+pub const COMPRESSED_SUFFIX: &str = ".gz";
+
+/// The file names `Font::load_font` tries for `name`, in order.
+fn font_file_names(name: &str) -> Vec<String> {
+    let mut names = vec![name.to_string()];
+    names.extend(
+        FONT_SUFFIXES
+            .iter()
+            .map(|suffix| format!("{}{}", name, suffix)),
+    );
+    #[cfg(feature = "compressed")]
+    names.extend(
+        FONT_SUFFIXES
+            .iter()
+            .map(|suffix| format!("{}{}{}", name, suffix, COMPRESSED_SUFFIX)),
+    );
+    names
+}
+
+fn is_font_file(name: &str) -> bool {
+    #[cfg(feature = "compressed")]
+    let name = name.strip_suffix(COMPRESSED_SUFFIX).unwrap_or(name);
+    FONT_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Unpack a zipped font, as figlet reads them, or a gzipped one. Anything
+/// else is returned as it is.
+#[cfg(feature = "compressed")]
+fn decompress(data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    use flate2::read::{DeflateDecoder, GzDecoder};
+    use std::io::{Error, ErrorKind};
+
+    let mut out = vec![];
+    if data.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(&data[..]).read_to_end(&mut out)?;
+    } else if data.starts_with(b"PK\x03\x04") {
+        // The font is the first file in the archive, after its local
+        // header.
+        let invalid = || Error::new(ErrorKind::InvalidData, "invalid zip archive");
+        let field = |at: usize, len: usize| {
+            let bytes = data.get(at..at + len).ok_or_else(invalid)?;
+            Ok::<_, Error>(bytes.iter().rev().fold(0, |n, &b| n << 8 | usize::from(b)))
+        };
+        let start = 30 + field(26, 2)? + field(28, 2)?;
+        let body = data.get(start..).ok_or_else(invalid)?;
+        match field(8, 2)? {
+            0 => out.extend_from_slice(body.get(..field(18, 4)?).ok_or_else(invalid)?),
+            8 => {
+                DeflateDecoder::new(body).read_to_end(&mut out)?;
+            }
+            method => {
+                let message = format!("unsupported zip compression method {}", method);
+                return Err(Error::new(ErrorKind::InvalidData, message));
+            }
+        }
+    } else {
+        return Ok(data);
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Font {
     pub name: String,
     pub font_head: FontOpts,
     pub meta_data: String,
-    pub chars: HashMap<u16, Vec<Vec<char>>>,
-    rules: Rules,
+    /// Glyphs by code, kept in order so everything listing them is stable.
+    pub(crate) chars: BTreeMap<i32, Glyph>,
+    pub(crate) rules: Rules,
 }
 
 impl Font {
-    pub fn load_font(name: &str) -> Result<Self, std::num::ParseIntError> {
-        let file_name: PathBuf = [".", "fonts", name].iter().collect();
-        let mut file = File::open(file_name).unwrap();
-        let mut content = String::new();
-        file.read_to_string(&mut content).unwrap();
-        Font::parse_font(name, &content)
+    pub fn load_font(name: &str) -> Result<Self, FontError> {
+        Font::load_font_with(name, &ParseOptions::default())
     }
 
-    pub fn parse_font(name: &str, data: &str) -> Result<Self, std::num::ParseIntError> {
-        let lines = &mut data.lines();
+    /// Short for `load_font`, e.g. `Font::load("slant")`.
+    pub fn load(name: &str) -> Result<Self, FontError> {
+        Font::load_font(name)
+    }
 
-        let font_head = FontOpts::parse(lines.next().unwrap())?;
+    /// Load a font from ./fonts. As with figlet, the name is tried as
+    /// given, then with each of `FONT_SUFFIXES`, and failing that the
+    /// file names are matched ignoring case, so "slant" finds "Slant.flf".
+    /// With the `compressed` feature, gzipped fonts are tried last, and
+    /// zipped or gzipped font files are unpacked.
+    pub fn load_font_with(name: &str, options: &ParseOptions) -> Result<Self, FontError> {
+        let dir: PathBuf = [".", "fonts"].iter().collect();
+        let candidates = font_file_names(name);
+        let found = candidates
+            .iter()
+            .find(|candidate| dir.join(candidate).is_file())
+            .cloned()
+            .or_else(|| {
+                std::fs::read_dir(&dir)
+                    .ok()?
+                    .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                    .filter(|file| candidates.iter().any(|c| c.eq_ignore_ascii_case(file)))
+                    .min()
+            });
+        // Nothing matched, so report the name as given.
+        let file_name = found.unwrap_or_else(|| name.to_string());
+        let mut file = File::open(dir.join(&file_name))?;
+        let mut content = vec![];
+        file.read_to_end(&mut content)?;
+        #[cfg(feature = "compressed")]
+        let content = decompress(content)?;
+        Font::parse_bytes(&file_name, &content, options)
+    }
 
-        let char_nums = (32..126).chain(vec![196, 214, 220, 228, 246, 252, 223].into_iter());
+    /// Names of the fonts `load_font` can find, in order.
+    pub fn installed() -> Result<Vec<String>, FontError> {
+        let mut names = vec![];
+        for entry in std::fs::read_dir([".", "fonts"].iter().collect::<PathBuf>())? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if is_font_file(&name) {
+                names.push(name);
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
 
-        let comment: String = lines
-            .take(font_head.comment_lines)
-            .collect::<Vec<&str>>()
-            .join("\n");
+    pub fn parse_bytes(name: &str, data: &[u8], options: &ParseOptions) -> Result<Self, FontError> {
+        let data = options.encoding.decode(data)?;
+        Font::parse_font_with(name, &data, options)
+    }
 
-        let line_vec: Vec<_> = lines
-            .map(|l| {
-                let last_char = &l[l.len() - 1..];
-                l.replace(last_char, "").chars().collect::<Vec<_>>()
-            })
-            .collect();
+    pub fn parse_font(name: &str, data: &str) -> Result<Self, FontError> {
+        Font::parse_font_with(name, data, &ParseOptions::default())
+    }
 
-        let fig_chars: HashMap<u16, Vec<_>> = char_nums
-            .zip(line_vec.chunks(font_head.height).map(|l| l.to_vec()))
-            .collect();
+    pub fn parse_font_with(
+        name: &str,
+        data: &str,
+        options: &ParseOptions,
+    ) -> Result<Self, FontError> {
+        Font::parse_font_with_warnings(name, data, options).map(|(font, _)| font)
+    }
 
-        let rules = Font::get_layout(font_head.full_layout, font_head.old_layout);
+    /// Like `parse_font_with`, but also listing what was wrong with the
+    /// font and worked around: short or tall glyphs are padded, and a
+    /// glyph with an unreadable code tag is skipped rather than ending the
+    /// font there. In strict mode these are errors instead.
+    pub fn parse_font_with_warnings(
+        name: &str,
+        data: &str,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), FontError> {
+        let mut warnings = vec![];
+        let lines: Vec<&str> = data.lines().collect();
 
-        Ok(Font {
+        let font_head = FontOpts::parse(lines.first().cloned().unwrap_or(""))?;
+        let height = font_head.height;
+        let limits = &options.limits;
+        FontLimits::check(limits.height, FontLimit::Height, height)?;
+        FontLimits::check(
+            limits.max_length,
+            FontLimit::MaxLength,
+            font_head.max_length,
+        )?;
+        if let Some(count) = font_head.codetag_count {
+            FontLimits::check(limits.codetags, FontLimit::Codetags, count)?;
+        }
+        let mut glyph_bytes = 0;
+        let mut add_glyph = |glyph: &Glyph| {
+            let cells: usize = glyph.rows().iter().map(Vec::len).sum();
+            glyph_bytes += cells * std::mem::size_of::<char>();
+            FontLimits::check(limits.glyph_bytes, FontLimit::GlyphBytes, glyph_bytes)
+        };
+
+        let comment_end = (1 + font_head.comment_lines).min(lines.len());
+        let comment = lines[1.min(comment_end)..comment_end].join("\n");
+
+        let char_nums = (32..=126).chain(DEUTSCH_CODES.iter().cloned());
+
+        let mut pos = comment_end;
+        let mut fig_chars = BTreeMap::new();
+        for code in char_nums {
+            if pos >= lines.len() {
+                break;
+            }
+            let (glyph, len) =
+                read_glyph(&lines[pos..], pos + 1, height, code, options, &mut warnings)?;
+            add_glyph(&glyph)?;
+            fig_chars.insert(code, glyph);
+            pos += len;
+        }
+        let mut codetags = 0;
+        // Code-tagged glyphs: a line starting with the code, then the art.
+        while pos < lines.len() {
+            if lines[pos].trim().is_empty() {
+                pos += 1;
+                continue;
+            }
+            let tag_line = pos + 1;
+            let code = match parse_code_tag(lines[pos]) {
+                Some(code) => code,
+                None if options.strict => return Err(FontError::BadCodeTag { line: tag_line }),
+                None => {
+                    warnings.push(ParseWarning::BadCodeTag { line: tag_line });
+                    pos += 1 + block_len(&lines[pos + 1..], height);
+                    continue;
+                }
+            };
+            codetags += 1;
+            FontLimits::check(limits.codetags, FontLimit::Codetags, codetags)?;
+            pos += 1;
+            let (glyph, len) =
+                read_glyph(&lines[pos..], pos + 1, height, code, options, &mut warnings)?;
+            add_glyph(&glyph)?;
+            // -1 is reserved and never names a glyph.
+            if code == -1 {
+                warnings.push(ParseWarning::ReservedCode { line: tag_line });
+            } else {
+                fig_chars.insert(code, glyph);
+            }
+            pos += len;
+        }
+
+        let rules = Rules::from_layout(font_head.full_layout, font_head.old_layout as i32);
+
+        let font = Font {
             name: String::from(name),
             font_head,
             meta_data: comment,
             chars: fig_chars,
             rules,
-        })
+        };
+        Ok((font, warnings))
     }
 
-    fn get_layout(full_layout: Option<isize>, old_layout: isize) -> Rules {
-        let mut horizontal_rules = vec![];
-        let mut vertical_rules = vec![];
-        let mut horizontal_layout: Option<LayoutMode> = None;
-        let mut vertical_layout: Option<LayoutMode> = None;
-        let mut ly = full_layout.unwrap_or(old_layout);
-
-        let rules: Vec<_> = SmushingRule::iter().collect();
-        for code in rules.into_iter().rev() {
-            if ly >= code as isize {
-                ly -= code as isize;
-                match code.get_type() {
-                    LayoutType::Horizontal => {
-                        horizontal_rules.push(code);
-                        horizontal_layout = Some(code.get_mode());
-                    }
-                    LayoutType::Vertical => {
-                        vertical_rules.push(code);
-                        vertical_layout = Some(code.get_mode());
-                    }
-                }
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.chars.get(&(c as i32))
+    }
+
+    /// The layout the font asks for: its horizontal and vertical modes and
+    /// the smushing rules in effect, as decoded from the header.
+    pub fn layout(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// The glyph stored under a FIGfont code, which for codes that aren't
+    /// characters, such as negative ones, is the only way to reach it.
+    pub fn glyph_by_code(&self, code: i32) -> Option<&Glyph> {
+        self.chars.get(&code)
+    }
+
+    /// Every glyph in the font, in code order.
+    pub fn glyphs(&self) -> impl Iterator<Item = &Glyph> {
+        self.chars.values()
+    }
+
+    /// Number of glyphs in the font.
+    pub fn glyph_count(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Add `glyph` under its code, returning the glyph it replaces.
+    pub fn insert_glyph(&mut self, glyph: Glyph) -> Option<Glyph> {
+        self.chars.insert(glyph.code(), glyph)
+    }
+
+    /// Whether the font has a glyph for `c`.
+    pub fn supports(&self, c: char) -> bool {
+        self.chars.contains_key(&(c as i32))
+    }
+
+    /// The characters of `message` the font has no glyph for, with their
+    /// byte offsets. As when rendering, marks are drawn with the character
+    /// before them, so only the first character of a cluster counts.
+    pub fn unsupported(&self, message: &str) -> Vec<(usize, char)> {
+        let mut offset = 0;
+        let mut missing = vec![];
+        for cluster in shaping::clusters(message) {
+            let c = cluster.chars().next().unwrap_or_default();
+            if !self.supports(c) {
+                missing.push((offset, c));
+            }
+            offset += cluster.len();
+        }
+        missing
+    }
+
+    /// Every character the font has a glyph for, in code point order.
+    /// Negative codes, which stand for no character, are left out.
+    pub fn coverage(&self) -> impl Iterator<Item = char> + '_ {
+        self.chars
+            .keys()
+            .filter_map(|&code| u32::try_from(code).ok().and_then(char::from_u32))
+    }
+
+    pub fn convert(&self, message: &str) -> String {
+        self.render(message).to_string()
+    }
+
+    /// Like `convert`, but a single character the font has is borrowed
+    /// straight from its glyph instead of being rendered.
+    pub fn convert_cow(&self, message: &str) -> Cow<'_, str> {
+        let mut chars = message.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if let Some(glyph) = self.glyph(c) {
+                return Cow::Borrowed(glyph.text(self.font_head.hardblank));
             }
         }
-        if horizontal_layout.is_none() {
-            if old_layout == 0 {
-                horizontal_layout = Some(LayoutMode::Fitting);
-                vertical_rules.push(SmushingRule::HorizontalFitting);
-            } else if old_layout == -1 {
-                horizontal_layout = Some(LayoutMode::FullWidth);
+        Cow::Owned(self.convert(message))
+    }
+
+    pub fn render(&self, message: &str) -> FIGure {
+        let mut figure = self.render_raw(message);
+        figure.replace(self.font_head.hardblank, ' ');
+        figure
+    }
+
+    /// Like `render`, but hardblanks are left in the output instead of
+    /// being turned into spaces.
+    pub fn render_raw(&self, message: &str) -> FIGure {
+        self.compose(
+            message,
+            &Compose {
+                rtl: self.font_head.print_direction == 1,
+                ..Compose::default()
+            },
+        )
+    }
+
+    /// Lay out `message` left to right, or right to left when `options.rtl`
+    /// is set so the first character ends up rightmost.
+    pub(crate) fn compose(&self, message: &str, options: &Compose) -> FIGure {
+        let mut rows = vec![];
+        self.compose_into(
+            message,
+            options,
+            &mut rows,
+            &mut Scratch::default(),
+            None,
+            None,
+        );
+        FIGure::new(rows)
+    }
+
+    /// Like `compose`, but also saying for every cell the byte offset in
+    /// `message` of the character whose glyph last inked it.
+    pub(crate) fn compose_traced(
+        &self,
+        message: &str,
+        options: &Compose,
+    ) -> (FIGure, Vec<Vec<Option<usize>>>) {
+        let mut rows = vec![];
+        let mut owners = vec![];
+        self.compose_into(
+            message,
+            options,
+            &mut rows,
+            &mut Scratch::default(),
+            Some(&mut owners),
+            None,
+        );
+        (FIGure::new(rows), owners)
+    }
+
+    /// Like `compose`, but reusing `rows` for the output and `units` as
+    /// scratch space, so that no allocation is needed once they've grown.
+    /// `owners`, when given, is filled in as by `compose_traced`.
+    pub(crate) fn compose_into(
+        &self,
+        message: &str,
+        options: &Compose,
+        rows: &mut Vec<Vec<char>>,
+        scratch: &mut Scratch,
+        mut owners: Option<&mut Vec<Vec<Option<usize>>>>,
+        mut junctions: Option<&mut Vec<Junction>>,
+    ) {
+        let Scratch {
+            units,
+            offsets,
+            pairs,
+            trailing,
+        } = scratch;
+        units.clear();
+        offsets.clear();
+        let mut offset = 0;
+        let mut rest = message;
+        while !rest.is_empty() {
+            offsets.resize(units.len(), offset);
+            offset = message.len() - rest.len();
+            let ligature = options
+                .ligatures
+                .iter()
+                .filter(|(seq, code)| {
+                    rest.starts_with(seq.as_str()) && self.chars.contains_key(code)
+                })
+                .max_by_key(|(seq, _)| seq.len());
+            if let Some((seq, code)) = ligature.filter(|(seq, _)| !seq.is_empty()) {
+                units.push((*code, None));
+                rest = &rest[seq.len()..];
+                continue;
             }
-        } else {
-            let hl = horizontal_layout.as_ref().unwrap();
-            if *hl == LayoutMode::ControlledSmush {
-                horizontal_rules.retain(|r| *r != SmushingRule::HorizontalSmushing);
+            // A cluster is drawn as its first character, whose glyph stands
+            // for any marks that follow.
+            let cluster = shaping::clusters(rest).next().unwrap_or(rest);
+            rest = &rest[cluster.len()..];
+            let c = cluster.chars().next().unwrap_or_default();
+            if self.chars.contains_key(&(c as i32)) {
+                units.push((c as i32, Some(c)));
+            } else {
+                self.missing(cluster, options.missing, units);
             }
         }
+        offsets.resize(units.len(), offset);
+        if options.rtl {
+            units.reverse();
+            offsets.reverse();
+        }
 
-        if vertical_layout.is_none() {
-            vertical_layout = Some(LayoutMode::FullWidth);
-        } else {
-            let vl = vertical_layout.as_ref().unwrap();
-            if *vl == LayoutMode::ControlledSmush {
-                vertical_rules.retain(|r| *r != SmushingRule::VerticalSmushing);
+        let rules = options.rules.unwrap_or(&self.rules);
+        let hardblank = self.font_head.hardblank;
+        rows.resize_with(self.font_head.height, Vec::new);
+        for row in rows.iter_mut() {
+            row.clear();
+        }
+        let touches = |c1, c2| touches(rules, c1, c2, hardblank);
+        let merge = |c1, c2| merge(rules, c1, c2, hardblank, options.rtl);
+        // Text repeats a lot, so the overlap of each pair is worked out once
+        // per render.
+        pairs.clear();
+        trailing.clear();
+        trailing.resize(rows.len(), 0);
+        if let Some(owners) = owners.as_deref_mut() {
+            owners.clear();
+            owners.resize(rows.len(), vec![]);
+        }
+        // The last glyph code, and whether the line's edge is all its own.
+        let mut last: Option<(i32, bool)> = None;
+        let mut prev = None;
+        let mut prev_width = None;
+        for (i, &(code, c)) in units.iter().enumerate() {
+            if options.cancel.is_some_and(CancelToken::is_cancelled)
+                || options
+                    .max_width
+                    .is_some_and(|max| rows.iter().any(|row| row.len() > max))
+            {
+                break;
+            }
+            let glyph = &self.chars[&code];
+            let touches: &dyn Fn(char, char) -> bool = match narrow(prev_width, glyph) {
+                true => &|_, _| false,
+                false => &touches,
+            };
+            let adjust = match (prev, c) {
+                (Some(p), Some(c)) => options
+                    .kerning
+                    .and_then(|kerning| kerning.get(&(p, c)))
+                    .cloned()
+                    .unwrap_or(0),
+                _ => 0,
+            };
+            let overlay = match last {
+                Some((last, true)) => *pairs
+                    .entry((last, code))
+                    .or_insert_with(|| margin_overlay(rules, rows, trailing, glyph, touches)),
+                _ => margin_overlay(rules, rows, trailing, glyph, touches),
+            };
+            let line = rows.iter().map(|row| row.len()).min().unwrap_or(0);
+            let overlay = overlay.min(line) as isize - adjust;
+            if overlay < 0 {
+                for (row, blank) in rows.iter_mut().zip(trailing.iter_mut()) {
+                    row.extend(std::iter::repeat_n(' ', overlay.unsigned_abs()));
+                    *blank += overlay.unsigned_abs();
+                }
+            }
+            let shortest = rows
+                .iter()
+                .chain(glyph.rows())
+                .map(|row| row.len())
+                .min()
+                .unwrap_or(0);
+            let overlay = (overlay.max(0) as usize).min(shortest);
+            if let Some(owners) = owners.as_deref_mut() {
+                for ((owner, row), new) in owners.iter_mut().zip(rows.iter()).zip(glyph.rows()) {
+                    let start = row.len() - overlay;
+                    owner.resize(start + new.len(), None);
+                    for (k, &c) in new.iter().enumerate() {
+                        if c != ' ' {
+                            owner[start + k] = Some(offsets[i]);
+                        }
+                    }
+                }
+            }
+            if let (Some(junctions), Some(&left)) =
+                (junctions.as_deref_mut(), units.get(i.wrapping_sub(1)))
+            {
+                let right = (code, c);
+                junctions.push(debug::junction(
+                    rules, hardblank, rows, glyph, overlay, left, right,
+                ));
             }
+            let clean = place(rows, trailing, glyph, overlay, &merge);
+            last = Some((code, clean));
+            prev = c;
+            prev_width = Some(glyph.width());
         }
+    }
 
-        Rules {
-            horizontal_layout: horizontal_layout.unwrap(),
-            vertical_layout: vertical_layout.unwrap(),
-            horizontal_rules,
-            vertical_rules,
+    /// Render `message` into `buf`, reusing its storage. Once `buf` has
+    /// held a banner as big as this one, nothing is allocated.
+    pub fn render_into(&self, message: &str, buf: &mut FIGureBuf) {
+        let options = Compose {
+            rtl: self.font_head.print_direction == 1,
+            ..Compose::default()
+        };
+        self.compose_into(
+            message,
+            &options,
+            &mut buf.rows,
+            &mut buf.scratch,
+            None,
+            None,
+        );
+        let hardblank = self.font_head.hardblank;
+        buf.text.clear();
+        for (i, row) in buf.rows.iter_mut().enumerate() {
+            if i > 0 {
+                buf.text.push('\n');
+            }
+            for c in row.iter_mut() {
+                if *c == hardblank {
+                    *c = ' ';
+                }
+                buf.text.push(*c);
+            }
         }
     }
 
-    pub fn convert(&self, message: &str) -> String {
-        let mut result = vec![vec![' '; 0]; self.font_head.height];
-        for c in message.chars() {
-            let figchar = self.chars.get(&(c as u32 as u16)).unwrap();
-            self.add_char(&mut result, figchar);
+    /// Push whatever `policy` draws for `c`, which the font doesn't have.
+    #[cfg_attr(not(feature = "emoji"), allow(unused_variables))]
+    fn missing(&self, cluster: &str, policy: MissingPolicy, units: &mut Vec<(i32, Option<char>)>) {
+        let fallback = match policy {
+            MissingPolicy::Default => 0,
+            MissingPolicy::Skip => return,
+            MissingPolicy::Placeholder(placeholder) => placeholder as i32,
+            #[cfg(feature = "emoji")]
+            MissingPolicy::Shortcode => {
+                // Variation selectors and joiners inside emoji sequences
+                // have nothing to show on their own.
+                if cluster == "\u{fe0f}" || cluster == "\u{200d}" {
+                    return;
+                }
+                let emoji =
+                    emojis::get(cluster).or_else(|| emojis::get(&cluster.replace('\u{fe0f}', "")));
+                if let Some(shortcode) = emoji.and_then(|e| e.shortcode()) {
+                    for c in format!(":{}:", shortcode).chars() {
+                        if self.chars.contains_key(&(c as i32)) {
+                            units.push((c as i32, Some(c)));
+                        }
+                    }
+                    return;
+                }
+                0
+            }
+        };
+        if self.chars.contains_key(&fallback) {
+            units.push((fallback, None));
         }
-        result
-            .into_iter()
-            .map(|row| row.into_iter().collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n")
     }
 
-    fn add_char(&self, chars: &mut Vec<Vec<char>>, figchar: &[Vec<char>]) {
-        let overlay = self.calc_overlay(chars, figchar) as usize;
-        for (cs1, cs2) in chars.iter_mut().zip(figchar.to_owned().iter_mut()) {
+    /// Add one glyph the slow way, scanning every row for the overlap;
+    /// tests check `compose` against it.
+    #[cfg(test)]
+    pub(crate) fn add_char(&self, chars: &mut [Vec<char>], figchar: &Glyph) {
+        self.add_char_kerned(&self.rules, chars, figchar, 0);
+    }
+
+    /// Like `add_char`, following `rules`, with the gap widened by `adjust`
+    /// columns, or narrowed when it's negative. Strokes pushed together
+    /// that the rules can't smush keep the new one.
+    #[cfg(test)]
+    pub(crate) fn add_char_kerned(
+        &self,
+        rules: &Rules,
+        chars: &mut [Vec<char>],
+        figchar: &Glyph,
+        adjust: isize,
+    ) {
+        let overlay = self.calc_overlay_with(rules, chars, figchar) as isize - adjust;
+        let figchar = figchar.rows();
+        if overlay < 0 {
+            for row in chars.iter_mut() {
+                row.extend(std::iter::repeat_n(' ', overlay.unsigned_abs()));
+            }
+        }
+        let shortest = chars
+            .iter()
+            .chain(figchar.iter())
+            .map(|row| row.len())
+            .min()
+            .unwrap_or(0);
+        let overlay = (overlay.max(0) as usize).min(shortest);
+        for (cs1, cs2) in chars.iter_mut().zip(figchar.iter()) {
             let cs1l = cs1.len();
-            let _cs2l = cs2.len();
-            for k in 0..overlay {
+            for (k, &c2) in cs2.iter().enumerate().take(overlay) {
                 let col = cs1l - overlay + k;
                 let c1 = cs1[col];
-                let c2 = cs2[k];
-                let smushed = self
-                    .rules
+                cs1[col] = rules
                     .smush_horizontal(c1, c2, self.font_head.hardblank)
-                    .unwrap();
-                cs1[col] = smushed;
+                    .unwrap_or(if c2 == ' ' { c1 } else { c2 });
             }
             cs1.extend_from_slice(&cs2[overlay..]);
         }
     }
 
-    fn calc_overlay(&self, chars: &[Vec<char>], figchar: &[Vec<char>]) -> u32 {
+    pub(crate) fn calc_overlay_with(
+        &self,
+        rules: &Rules,
+        chars: &[Vec<char>],
+        figchar: &Glyph,
+    ) -> u32 {
+        let figchar = figchar.rows();
         assert_eq!(chars.len(), figchar.len());
-        if self.rules.horizontal_layout == LayoutMode::FullWidth {
+        if rules.horizontal_layout == LayoutMode::FullWidth {
             return 0;
         }
 
-        let mut max_overlay = chars[0].len() as u32;
+        // Ragged or padded glyphs can have short rows; never overlap more
+        // than the shortest row on either side.
+        let mut max_overlay = chars
+            .iter()
+            .chain(figchar.iter())
+            .map(|row| row.len())
+            .min()
+            .unwrap_or(0) as u32;
 
         for (cs, fs) in chars.iter().zip(figchar.iter()) {
             let emptys1 = cs.iter().rev().take_while(|c| **c == ' ').count();
@@ -213,7 +1130,7 @@ impl Font {
             let mut overlay: u32 = emptys1 as u32 + emptys2 as u32;
             if emptys1 < cs.len()
                 && emptys2 < fs.len()
-                && (self.rules.horizontal_layout == LayoutMode::UniversalSmush
+                && (rules.horizontal_layout == LayoutMode::UniversalSmush
                     && SmushingRule::HorizontalSmushing
                         .smush(
                             cs[cs.len() - 1 - emptys1],
@@ -221,7 +1138,7 @@ impl Font {
                             self.font_head.hardblank,
                         )
                         .is_some()
-                    || self.rules.smushes_horizontal(
+                    || rules.smushes_horizontal(
                         cs[cs.len() - 1 - emptys1],
                         fs[emptys2],
                         self.font_head.hardblank,
@@ -238,126 +1155,527 @@ impl Font {
     }
 }
 
+/// Whether two ink cells may be pushed into the same column under `rules`,
+/// as `Font::calc_overlay_with` decides it.
+pub(crate) fn touches(rules: &Rules, c1: char, c2: char, hardblank: char) -> bool {
+    rules.horizontal_layout == LayoutMode::UniversalSmush
+        && SmushingRule::HorizontalSmushing
+            .smush(c1, c2, hardblank)
+            .is_some()
+        || rules.smushes_horizontal(c1, c2, hardblank)
+}
+
+/// What two overlapping cells become under `rules`: the smushed character,
+/// or else whichever isn't blank, the right one if neither is. With the
+/// `compat` feature, universal smushing right to left keeps the left one,
+/// which figlet 2.2.5 added last.
+pub(crate) fn merge(rules: &Rules, c1: char, c2: char, hardblank: char, rtl: bool) -> char {
+    if cfg!(feature = "compat")
+        && rtl
+        && rules.horizontal_layout == LayoutMode::UniversalSmush
+        && ![' ', hardblank].contains(&c1)
+        && c2 != ' '
+    {
+        return c1;
+    }
+    rules
+        .smush_horizontal(c1, c2, hardblank)
+        .unwrap_or(if c2 == ' ' { c1 } else { c2 })
+}
+
+/// Whether only fitting applies between a glyph `prev_width` wide and
+/// `glyph`. With the `compat` feature, as in figlet 2.2.5, glyphs less
+/// than two columns wide never smush.
+pub(crate) fn narrow(prev_width: Option<usize>, glyph: &Glyph) -> bool {
+    cfg!(feature = "compat") && (glyph.width() < 2 || prev_width.is_some_and(|width| width < 2))
+}
+
+/// `Font::calc_overlay_with` worked out from margins instead of scanning:
+/// `trailing` holds the blank run at the end of each row of the line.
+/// The result isn't capped by the length of the line's rows, which
+/// callers still have to do.
+pub(crate) fn margin_overlay(
+    rules: &Rules,
+    rows: &[Vec<char>],
+    trailing: &[usize],
+    glyph: &Glyph,
+    touches: &dyn Fn(char, char) -> bool,
+) -> usize {
+    let (margins, glyph) = (glyph.margins(), glyph.rows());
+    if rules.horizontal_layout == LayoutMode::FullWidth {
+        return 0;
+    }
+    let mut max_overlay = glyph.iter().map(|row| row.len()).min().unwrap_or(0);
+    for (i, (row, new)) in rows.iter().zip(glyph.iter()).enumerate() {
+        let (blank1, blank2) = (trailing[i], margins.leading[i]);
+        let mut overlay = blank1 + blank2;
+        if blank1 < row.len()
+            && blank2 < new.len()
+            && touches(row[row.len() - 1 - blank1], new[blank2])
+        {
+            overlay += 1;
+        }
+        max_overlay = max_overlay.min(overlay);
+    }
+    max_overlay
+}
+
+/// Append `glyph` to the line `overlay` columns in, with `merge` deciding
+/// what overlapping cells become, and keep `trailing` up to date. Returns
+/// whether every row now ends in the glyph's own ink, in which case the
+/// next overlap depends on this glyph alone.
+pub(crate) fn place(
+    rows: &mut [Vec<char>],
+    trailing: &mut [usize],
+    glyph: &Glyph,
+    overlay: usize,
+    merge: &dyn Fn(char, char) -> char,
+) -> bool {
+    let (margins, glyph) = (glyph.margins(), glyph.rows());
+    let mut clean = true;
+    for (i, (row, new)) in rows.iter_mut().zip(glyph.iter()).enumerate() {
+        let start = row.len() - overlay;
+        for (k, &c2) in new.iter().enumerate().take(overlay) {
+            row[start + k] = merge(row[start + k], c2);
+        }
+        row.extend_from_slice(&new[overlay..]);
+        let tail = new.len() - overlay;
+        trailing[i] = if margins.trailing[i] < tail {
+            margins.trailing[i]
+        } else {
+            // The new part is blank, so the end of the row is decided by
+            // the cells that were merged.
+            clean = false;
+            match row[start..start + overlay].iter().rposition(|c| *c != ' ') {
+                Some(ink) => tail + overlay - 1 - ink,
+                None => tail + trailing[i],
+            }
+        };
+    }
+    clean
+}
+
+impl FromStr for Font {
+    type Err = FontError;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        Font::parse_font("", data)
+    }
+}
+
+impl TryFrom<&[u8]> for Font {
+    type Error = FontError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Font::parse_bytes("", data, &ParseOptions::default())
+    }
+}
+
+#[test]
+fn from_str() {
+    let data = include_str!("../fonts/Standard.flf");
+    let f: Font = data.parse().unwrap();
+    assert_eq!(
+        f.convert("Hi"),
+        Font::load_font("Standard.flf").unwrap().convert("Hi")
+    );
+    let f = Font::try_from(data.as_bytes()).unwrap();
+    assert!(f.glyph('H').is_some());
+
+    assert!(matches!("".parse::<Font>(), Err(FontError::ParseInt(_))));
+    assert!(matches!(
+        Font::try_from(&b"flf2a\xff"[..]),
+        Err(FontError::ParseInt(_))
+    ));
+}
+
+#[test]
+fn load_latin1_font() {
+    let f = Font::load_font("Bear.flf").unwrap();
+    assert!(f.glyph('B').is_some());
+    let utf8 = ParseOptions::new().encoding(Encoding::Utf8);
+    assert!(matches!(
+        Font::load_font_with("Bear.flf", &utf8),
+        Err(FontError::Utf8(_))
+    ));
+    assert!(matches!(
+        Font::load_font("no such font.flf"),
+        Err(FontError::Io(_))
+    ));
+}
+
+#[test]
+fn glyph_height_mismatch() {
+    let mut data = String::from("flf2a$ 2 1 4 -1 0\n");
+    for _ in 32..=126 {
+        data.push_str("ab@\ncd@@\n");
+    }
+    let short = data.replacen("ab@\ncd@@\n", "ab@\ncd@@\nxy@@\n", 1);
+
+    let f = Font::parse_font("", &short).unwrap();
+    let rows = |c| f.glyph(c).unwrap().rows().to_vec();
+    assert_eq!(rows('!'), vec![vec!['x', 'y'], vec![' ', ' ']]);
+    assert_eq!(rows('"'), vec![vec!['a', 'b'], vec!['c', 'd']]);
+    assert_eq!(rows('~'), vec![vec!['a', 'b'], vec!['c', 'd']]);
+
+    let strict = ParseOptions::new().strict(true);
+    assert!(Font::parse_font_with("", &data, &strict).is_ok());
+    assert!(matches!(
+        Font::parse_font_with("", &short, &strict),
+        Err(FontError::GlyphHeight {
+            code: 33,
+            expected: 2,
+            found: 1
+        })
+    ));
+}
+
+#[test]
+fn parse_warnings() {
+    let mut data = String::from("flf2a$ 2 1 4 -1 0\n");
+    for _ in 0..102 {
+        data.push_str("ab@\ncd@@\n");
+    }
+    data.push_str("99999999999 TOO BIG\nxx@\nxx@@\n-1\nyy@\nyy@@\n0x100\nzz@@\n0x101\nef@\ngh@@\n");
+
+    let (f, warnings) = Font::parse_font_with_warnings("", &data, &ParseOptions::new()).unwrap();
+    assert_eq!(
+        warnings,
+        [
+            ParseWarning::BadCodeTag { line: 206 },
+            ParseWarning::ReservedCode { line: 209 },
+            ParseWarning::GlyphHeight {
+                line: 213,
+                code: 256,
+                expected: 2,
+                found: 1
+            },
+        ]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "line 206: invalid code tag, glyph skipped"
+    );
+    // The glyphs around the broken ones are all still there.
+    assert!(f.supports('~') && f.supports('\u{df}'));
+    assert_eq!(f.glyph('\u{100}').unwrap().rows()[0], ['z', 'z']);
+    assert_eq!(f.glyph('\u{101}').unwrap().rows()[1], ['g', 'h']);
+    assert_eq!(f, Font::parse_font("", &data).unwrap());
+
+    let strict = ParseOptions::new().strict(true);
+    assert!(matches!(
+        Font::parse_font_with_warnings("", &data, &strict),
+        Err(FontError::BadCodeTag { line: 206 })
+    ));
+    let standard = std::fs::read_to_string("fonts/Standard.flf").unwrap();
+    let (_, warnings) = Font::parse_font_with_warnings("Standard.flf", &standard, &strict).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn parse_limits() {
+    let standard = std::fs::read_to_string("fonts/Standard.flf").unwrap();
+    let parse = |data: &str, limits: FontLimits| {
+        Font::parse_font_with("", data, &ParseOptions::new().limits(limits))
+    };
+    assert!(parse(&standard, FontLimits::new().height(6).max_length(16)).is_ok());
+    assert!(matches!(
+        parse(&standard, FontLimits::new().height(5)),
+        Err(FontError::TooLarge {
+            what: FontLimit::Height,
+            value: 6,
+            limit: 5
+        })
+    ));
+    assert!(matches!(
+        parse(&standard, FontLimits::new().max_length(10)),
+        Err(FontError::TooLarge {
+            what: FontLimit::MaxLength,
+            ..
+        })
+    ));
+    assert!(matches!(
+        parse(&standard, FontLimits::new().glyph_bytes(4096)),
+        Err(FontError::TooLarge {
+            what: FontLimit::GlyphBytes,
+            ..
+        })
+    ));
+
+    // A header asking for enormous glyphs fails before any are read.
+    let hostile = "flf2a$ 99999999999 1 4 -1 0\n@@\n";
+    assert!(matches!(
+        parse(hostile, FontLimits::new().height(1000)),
+        Err(FontError::TooLarge {
+            what: FontLimit::Height,
+            ..
+        })
+    ));
+
+    // Code tags are counted even when the header doesn't declare them.
+    let mut tagged = String::from("flf2a$ 1 1 4 -1 0\n");
+    tagged.push_str(&"x@@\n".repeat(102));
+    for code in 300..310 {
+        tagged.push_str(&format!("{}\nx@@\n", code));
+    }
+    assert!(parse(&tagged, FontLimits::new().codetags(10)).is_ok());
+    let err = parse(&tagged, FontLimits::new().codetags(5)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "font has more than 5 code-tagged glyphs (6)"
+    );
+}
+
 #[test]
 fn basic_convert() {
-    let f = Font::load_font("standard.flf").unwrap();
+    let f = Font::load_font("Standard.flf").unwrap();
     // dbg!(&f.rules);
     let result = f.convert("FIGlet");
     println!("{}", &result);
 }
 
 #[test]
-fn get_layout_full_width() {
-    let l = Font::get_layout(Some(0), -1);
-    assert_eq!(l.horizontal_layout, LayoutMode::FullWidth);
-    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
-    assert_eq!(l.horizontal_rules.len(), 0);
-    assert_eq!(l.vertical_rules.len(), 0);
+fn hardblanks() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    assert!(f.render_raw("a b").to_string().contains('$'));
+    assert!(!f.render("a b").to_string().contains('$'));
+}
+
+#[test]
+fn render_into() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let mut buf = FIGureBuf::new();
+    f.render_into("Hello", &mut buf);
+    assert_eq!(buf.as_str(), f.convert("Hello"));
+    assert_eq!(buf.rows(), f.render("Hello").rows());
+    let before = allocations::count();
+    f.render_into("Hi", &mut buf);
+    f.render_into("Hello", &mut buf);
+    assert_eq!(allocations::count(), before);
+    assert_eq!(buf.as_str(), f.convert("Hello"));
+    f.render_into("Hi", &mut buf);
+    assert_eq!(buf.as_str(), f.convert("Hi"));
+}
+
+/// Counts the allocations made on each thread, for tests that promise
+/// not to make any.
+#[cfg(test)]
+mod allocations {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    struct Counting;
+
+    thread_local! {
+        static COUNT: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            COUNT.with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
 
-    let l = Font::get_layout(None, -1);
-    assert_eq!(l.horizontal_layout, LayoutMode::FullWidth);
-    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
-    assert_eq!(l.horizontal_rules.len(), 0);
-    assert_eq!(l.vertical_rules.len(), 0);
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+            COUNT.with(|count| count.set(count.get() + 1));
+            System.realloc(ptr, layout, size)
+        }
+    }
+
+    #[global_allocator]
+    static COUNTING: Counting = Counting;
+
+    /// Allocations made so far on this thread.
+    pub fn count() -> usize {
+        COUNT.with(Cell::get)
+    }
 }
 
 #[test]
-fn get_layout_kerning() {
-    let l = Font::get_layout(Some(64), 0);
-    assert_eq!(l.horizontal_layout, LayoutMode::Fitting);
-    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
-    assert_eq!(l.horizontal_rules.len(), 1);
+fn convert_cow() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    for message in ["A", " ", "AB", ""].iter() {
+        assert_eq!(f.convert_cow(message), f.convert(message));
+    }
+    assert!(matches!(f.convert_cow("A"), Cow::Borrowed(_)));
+    assert!(matches!(f.convert_cow("AB"), Cow::Owned(_)));
+}
+
+#[test]
+fn glyph_lookup() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    assert_eq!(f.glyph('A').unwrap().height(), 6);
+    assert!(f.glyph('\u{1f680}').is_none());
+}
+
+#[test]
+// FIGure's text cache is left out of its hash, so it's a safe key.
+#[allow(clippy::mutable_key_type)]
+fn clone_and_compare() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let copy = f.clone();
+    assert_eq!(copy, f);
+    assert_eq!(copy.rules, f.rules);
+    assert_eq!(copy.glyph('a'), f.glyph('a'));
+    assert_ne!(f.glyph('a'), f.glyph('b'));
+    assert_ne!(Font::load_font("Slant.flf").unwrap(), f);
+
+    let mut seen = std::collections::HashSet::new();
+    assert!(seen.insert(f.render("Hi")));
+    assert!(!seen.insert(copy.render("Hi")));
+}
+
+#[test]
+fn repeated_glyphs() {
+    // The memoized layout must match adding glyphs one by one.
+    for name in ["Standard.flf", "Slant.flf", "Banner.flf"] {
+        let f = Font::load_font(name).unwrap();
+        for message in ["aaaaaa", "/\\/\\/\\", "__ __ __", "|_|_|_ ..."] {
+            let mut rows = vec![vec![]; f.font_head.height()];
+            for c in message.chars() {
+                f.add_char(&mut rows, f.glyph(c).unwrap());
+            }
+            assert_eq!(f.render_raw(message), FIGure::new(rows), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn supports_and_coverage() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    assert!(f.supports('a') && f.supports('\u{c4}'));
+    assert!(!f.supports('\u{263a}'));
+    let coverage: Vec<char> = f.coverage().collect();
+    assert_eq!(coverage.first(), Some(&' '));
+    assert!(coverage.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(coverage.iter().all(|&c| f.supports(c)));
+    assert_eq!(
+        coverage.len(),
+        f.glyphs().filter(|glyph| glyph.code() >= 0).count()
+    );
+    assert_eq!(f.unsupported("ok"), []);
     assert_eq!(
-        l.horizontal_rules.get(0).unwrap(),
-        &SmushingRule::HorizontalFitting
+        f.unsupported("a\u{1f680}b\t"),
+        [(1, '\u{1f680}'), (6, '\t')]
     );
-    assert_eq!(l.vertical_rules.len(), 0);
+    // Two loads of a font list, and print, their glyphs the same way.
+    let again = Font::load_font("Standard.flf").unwrap();
+    assert_eq!(format!("{:?}", f), format!("{:?}", again));
 }
 
 #[test]
-fn get_layout_smushing() {
-    let l = Font::get_layout(Some(128), 0);
-    assert_eq!(l.horizontal_layout, LayoutMode::UniversalSmush);
-    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
-    assert_eq!(l.horizontal_rules.len(), 1);
+fn glyph_access() {
+    let mut f = Font::load_font("Standard.flf").unwrap();
+    let codes: Vec<i32> = f.glyphs().map(Glyph::code).collect();
+    assert_eq!(codes.len(), f.glyph_count());
+    assert!(codes.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(f.glyph_by_code('a' as i32), f.glyph('a'));
+
+    let rows = f.glyph('X').unwrap().rows().to_vec();
+    assert_eq!(f.insert_glyph(Glyph::new(-2, rows)), None);
     assert_eq!(
-        l.horizontal_rules.get(0).unwrap(),
-        &SmushingRule::HorizontalSmushing
+        f.glyph_by_code(-2).unwrap().rows(),
+        f.glyph('X').unwrap().rows()
     );
-    assert_eq!(l.vertical_rules.len(), 0);
+    assert_eq!(f.glyph_count(), codes.len() + 1);
 }
 
 #[test]
-fn get_layout_controlled_smushing_slant() {
-    // slant.flf
-    let l = Font::get_layout(Some(18319), 15);
-    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
-    assert_eq!(l.vertical_layout, LayoutMode::ControlledSmush);
-    assert_eq!(l.horizontal_rules.len(), 4);
-    assert_eq!(l.vertical_rules.len(), 3);
-
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalOppositePair));
-    assert!(l
+fn missing_clusters() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let placeholder = Compose {
+        missing: MissingPolicy::Placeholder('?'),
+        ..Compose::default()
+    };
+    let flag = f.compose("\u{1f1fa}\u{1f1f8}", &placeholder);
+    let accent = f.compose("e\u{301}", &placeholder);
+    if cfg!(feature = "graphemes") {
+        assert_eq!(flag, f.compose("?", &placeholder));
+        assert_eq!(accent, f.compose("e", &placeholder));
+    } else {
+        assert_eq!(flag, f.compose("??", &placeholder));
+        assert_eq!(accent, f.compose("e?", &placeholder));
+    }
+}
+
+#[test]
+fn font_layout() {
+    let standard = Font::load_font("Standard.flf").unwrap();
+    assert_eq!(
+        standard.layout().horizontal_layout,
+        LayoutMode::ControlledSmush
+    );
+    assert!(standard
+        .layout()
         .horizontal_rules
         .contains(&SmushingRule::HorizontalHierarchy));
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalUnderscore));
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalEqualChar));
+    let banner = Font::load_font("Banner.flf").unwrap();
+    assert_ne!(banner.layout(), standard.layout());
+}
+
+#[cfg(feature = "compat")]
+#[test]
+fn figlet_compat() {
+    let universal: Rules = "universal".parse().unwrap();
+    assert_eq!(universal.smush_horizontal('$', 'a', '$'), Some('a'));
+    assert_eq!(universal.smush_horizontal('a', '$', '$'), Some('a'));
+    assert_eq!(merge(&universal, 'a', 'b', '$', false), 'b');
+    assert_eq!(merge(&universal, 'a', 'b', '$', true), 'a');
+    assert_eq!(merge(&universal, '$', 'b', '$', true), 'b');
+
+    let thin = Glyph::new(0, vec![vec!['|']]);
+    let wide = Glyph::new(0, vec![vec!['|', '|']]);
+    assert!(narrow(Some(2), &thin));
+    assert!(narrow(Some(1), &wide));
+    assert!(!narrow(Some(2), &wide));
+    assert!(!narrow(None, &wide));
 
-    assert!(l.vertical_rules.contains(&SmushingRule::VerticalHierarchy));
-    assert!(l.vertical_rules.contains(&SmushingRule::VerticalUnderscore));
-    assert!(l.vertical_rules.contains(&SmushingRule::VerticalEqualChar));
+    let f = Font::load_font("Standard.flf").unwrap();
+    let wrapped = crate::render::Renderer::new(&f)
+        .width(30)
+        .render("wrap these words");
+    assert!(wrapped.width() <= 29);
 }
 
 #[test]
-fn get_layout_controlled_smushing_standard() {
-    // starndard.flf
-    let l = Font::get_layout(Some(24463), 15);
-    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
-    assert_eq!(l.vertical_layout, LayoutMode::ControlledSmush);
-    assert_eq!(l.horizontal_rules.len(), 4);
-    assert_eq!(l.vertical_rules.len(), 5);
-
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalOppositePair));
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalHierarchy));
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalUnderscore));
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalEqualChar));
+fn font_name_resolution() {
+    let slant = Font::load_font("Slant.flf").unwrap();
+    assert_eq!(Font::load("Slant").unwrap(), slant);
+    assert_eq!(Font::load("slant").unwrap(), slant);
+    assert_eq!(Font::load("SLANT.FLF").unwrap().name, "Slant.flf");
+    assert!(matches!(Font::load("no-such-font"), Err(FontError::Io(_))));
+}
 
-    assert!(l
-        .vertical_rules
-        .contains(&SmushingRule::VerticalVerticalLine));
-    assert!(l.vertical_rules.contains(&SmushingRule::VerticalHierarchy));
-    assert!(l.vertical_rules.contains(&SmushingRule::VerticalUnderscore));
-    assert!(l.vertical_rules.contains(&SmushingRule::VerticalEqualChar));
+#[cfg(feature = "compressed")]
+#[test]
+fn compressed_fonts() {
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use flate2::Compression;
 
-    let l = Font::get_layout(None, 15);
-    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
-    assert_eq!(l.horizontal_rules.len(), 4);
-    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
+    assert_eq!(font_file_names("slant")[3], "slant.flf.gz");
+    assert!(is_font_file("Slant.flf.gz"));
+    assert!(!is_font_file("Slant.gz"));
 
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalOppositePair));
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalHierarchy));
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalUnderscore));
-    assert!(l
-        .horizontal_rules
-        .contains(&SmushingRule::HorizontalEqualChar));
+    let data = std::fs::read("fonts/Slant.flf").unwrap();
+    assert_eq!(decompress(data.clone()).unwrap(), data);
+    let mut gz = GzEncoder::new(vec![], Compression::default());
+    gz.write_all(&data).unwrap();
+    assert_eq!(decompress(gz.finish().unwrap()).unwrap(), data);
+
+    // A zip archive holding just the font, with no sizes in its header as
+    // when written to a stream.
+    let mut deflate = DeflateEncoder::new(vec![], Compression::default());
+    deflate.write_all(&data).unwrap();
+    let mut zip = b"PK\x03\x04\x14\x00\x08\x00\x08\x00".to_vec();
+    zip.extend_from_slice(&[0; 16]);
+    zip.extend_from_slice(&[9, 0, 0, 0]);
+    zip.extend_from_slice(b"Slant.flf");
+    zip.extend_from_slice(&deflate.finish().unwrap());
+    assert_eq!(decompress(zip.clone()).unwrap(), data);
+    zip[8] = 12;
+    assert!(decompress(zip).is_err());
 }
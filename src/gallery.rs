@@ -0,0 +1,87 @@
+use crate::font::Font;
+use std::path::Path;
+
+/// A static HTML page showing `sample` in each of `fonts`, with an index
+/// linking to an anchor per font and the font's file name ready to copy.
+pub fn gallery(fonts: &[&Font], sample: &str) -> String {
+    let mut index = String::new();
+    let mut sections = String::new();
+    for font in fonts {
+        let name = escape(&font.name);
+        let anchor = anchor(&font.name);
+        index.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a></li>\n",
+            anchor,
+            escape(stem(&font.name))
+        ));
+        sections.push_str(&format!(
+            "<section id=\"{anchor}\">\n<h2><a href=\"#{anchor}\">{}</a></h2>\n\
+             <code class=\"name\">{name}</code>\n<pre>{}</pre>\n</section>\n",
+            escape(stem(&font.name)),
+            escape(&font.convert(sample)),
+            anchor = anchor,
+            name = name,
+        ));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>FIGlet fonts</title>\n<style>\n\
+         body {{ font-family: sans-serif; }}\n\
+         pre {{ font-family: monospace; line-height: 1.1; }}\n\
+         .name {{ user-select: all; }}\n\
+         </style>\n</head>\n<body>\n<h1>FIGlet fonts</h1>\n\
+         <ul>\n{}</ul>\n{}</body>\n</html>\n",
+        index, sections
+    )
+}
+
+fn stem(name: &str) -> &str {
+    Path::new(name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(name)
+}
+
+/// An id for the font's section: its name in lower case, with runs of
+/// anything but letters and digits turned into `-`.
+fn anchor(name: &str) -> String {
+    let mut id = String::new();
+    for c in stem(name).chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    id.trim_end_matches('-').to_string()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn html_gallery() {
+    let standard = Font::load_font("Standard.flf").unwrap();
+    let slant = Font::load_font("Small Slant.flf").unwrap();
+    let page = gallery(&[&standard, &slant], "<a>");
+    assert!(page.starts_with("<!DOCTYPE html>"));
+    assert!(page.contains("<a href=\"#small-slant\">Small Slant</a>"));
+    assert!(page.contains("<section id=\"standard\">"));
+    assert!(page.contains("<code class=\"name\">Small Slant.flf</code>"));
+    assert!(page.contains(&escape(&standard.convert("<a>"))));
+    assert_eq!(escape("<\"&\">"), "&lt;&quot;&amp;&quot;&gt;");
+    assert!(Font::installed()
+        .unwrap()
+        .contains(&"Standard.flf".to_string()));
+}
@@ -0,0 +1,139 @@
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+/// The art for a single FIGcharacter, with endmarks stripped, along with
+/// the metrics layout needs, worked out once when the glyph is made.
+#[derive(Debug, Default, Clone)]
+pub struct Glyph {
+    code: i32,
+    rows: Vec<Vec<char>>,
+    width: usize,
+    margins: Margins,
+    /// The glyph as printed on its own, built on first use.
+    text: OnceLock<String>,
+}
+
+// Everything else follows from the rows, so only they and the code take
+// part.
+impl PartialEq for Glyph {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code && self.rows == other.rows
+    }
+}
+
+impl Eq for Glyph {}
+
+impl Hash for Glyph {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code.hash(state);
+        self.rows.hash(state);
+    }
+}
+
+impl Glyph {
+    pub fn new(code: i32, rows: Vec<Vec<char>>) -> Self {
+        Glyph {
+            code,
+            width: rows.iter().map(|row| row.len()).max().unwrap_or(0),
+            margins: Margins::new(&rows),
+            rows,
+            text: OnceLock::new(),
+        }
+    }
+
+    /// The FIGfont code the glyph is stored under.
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    pub fn rows(&self) -> &[Vec<char>] {
+        &self.rows
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Length of the widest row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub(crate) fn margins(&self) -> &Margins {
+        &self.margins
+    }
+
+    /// The rows joined with newlines and `hardblank` shown as a space, which
+    /// is exactly what the glyph renders as on its own. Computed once.
+    pub(crate) fn text(&self, hardblank: char) -> &str {
+        self.text.get_or_init(|| {
+            let rows: Vec<String> = self
+                .rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&c| if c == hardblank { ' ' } else { c })
+                        .collect()
+                })
+                .collect();
+            rows.join("\n")
+        })
+    }
+
+    /// Number of blank columns on the left shared by every row.
+    pub fn left_blank(&self) -> usize {
+        self.margins.leading.iter().copied().min().unwrap_or(0)
+    }
+
+    /// Number of blank columns on the right shared by every row, counting
+    /// short rows as padded to the full width.
+    pub fn right_blank(&self) -> usize {
+        self.rows
+            .iter()
+            .zip(self.margins.trailing.iter())
+            .map(|(row, blank)| self.width - row.len() + blank)
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// The blank run at each end of every row of a glyph.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Margins {
+    pub leading: Vec<usize>,
+    pub trailing: Vec<usize>,
+}
+
+impl Margins {
+    pub fn new(glyph: &[Vec<char>]) -> Self {
+        let blanks = |row: &mut dyn Iterator<Item = &char>| row.take_while(|c| **c == ' ').count();
+        Margins {
+            leading: glyph.iter().map(|row| blanks(&mut row.iter())).collect(),
+            trailing: glyph
+                .iter()
+                .map(|row| blanks(&mut row.iter().rev()))
+                .collect(),
+        }
+    }
+}
+
+#[test]
+fn glyph_metrics() {
+    let g = Glyph::new(
+        'a' as i32,
+        vec![
+            "  __ ".chars().collect(),
+            " / /".chars().collect(),
+            "/_/  ".chars().collect(),
+        ],
+    );
+    assert_eq!(g.code(), 'a' as i32);
+    assert_eq!(g.height(), 3);
+    assert_eq!(g.width(), 5);
+    assert_eq!(g.left_blank(), 0);
+    assert_eq!(g.right_blank(), 1);
+
+    let space = Glyph::new(' ' as i32, vec![vec![' '; 2]; 2]);
+    assert_eq!(space.left_blank(), 2);
+    assert_eq!(space.right_blank(), 2);
+}
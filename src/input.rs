@@ -0,0 +1,182 @@
+use crate::render::{ascii_punctuation, strip_ansi, Case};
+use std::borrow::Cow;
+use std::fmt;
+
+/// A preprocessing step applied to the input text before glyph lookup.
+/// Filters that leave the text alone should return it borrowed.
+pub trait InputFilter: fmt::Debug {
+    fn apply<'a>(&self, message: &'a str) -> Cow<'a, str>;
+}
+
+impl InputFilter for Case {
+    fn apply<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        Cow::Owned(Case::apply(*self, message))
+    }
+}
+
+/// Typographic punctuation to ASCII; see `render::ascii_punctuation`.
+#[derive(Debug, Clone, Default)]
+pub struct AsciiPunctuation;
+
+impl InputFilter for AsciiPunctuation {
+    fn apply<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        if message.is_ascii() {
+            return Cow::Borrowed(message);
+        }
+        Cow::Owned(ascii_punctuation(message))
+    }
+}
+
+/// Removes ANSI escape sequences; see `render::strip_ansi`.
+#[derive(Debug, Clone, Default)]
+pub struct StripAnsi;
+
+impl InputFilter for StripAnsi {
+    fn apply<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        strip_ansi(message)
+    }
+}
+
+/// Terminal-style control characters, for input from old pipelines. Per
+/// line, `\r` returns to the start of the line and backspace steps back
+/// one character, and whatever comes next overwrites what's there. An
+/// underscore struck over a character keeps the character, so nroff-style
+/// underlining (`_\bx`) and bold (`x\bx`) come out as plain `x`. Tabs
+/// become spaces and other control characters except newlines are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct ControlChars;
+
+impl InputFilter for ControlChars {
+    fn apply<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        if !message.chars().any(|c| c.is_control() && c != '\n') {
+            return Cow::Borrowed(message);
+        }
+        let lines: Vec<String> = message
+            .split('\n')
+            .map(|line| {
+                let mut cells: Vec<char> = vec![];
+                let mut cursor: usize = 0;
+                for c in line.chars() {
+                    let c = match c {
+                        '\r' => {
+                            cursor = 0;
+                            continue;
+                        }
+                        '\x08' => {
+                            cursor = cursor.saturating_sub(1);
+                            continue;
+                        }
+                        '\t' => ' ',
+                        c if c.is_control() => continue,
+                        c => c,
+                    };
+                    match cells.get_mut(cursor) {
+                        Some(cell) if c == '_' && *cell != ' ' => {}
+                        Some(cell) => *cell = c,
+                        None => cells.push(c),
+                    }
+                    cursor += 1;
+                }
+                cells.into_iter().collect()
+            })
+            .collect();
+        Cow::Owned(lines.join("\n"))
+    }
+}
+
+/// Reorders mixed left-to-right and right-to-left text, such as Hebrew in
+/// an English sentence, from logical into visual order. `rtl` sets the
+/// base direction of each line.
+#[cfg(feature = "bidi")]
+#[derive(Debug, Clone, Default)]
+pub struct BidiReorder {
+    pub rtl: bool,
+}
+
+#[cfg(feature = "bidi")]
+impl InputFilter for BidiReorder {
+    fn apply<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        use unicode_bidi::{BidiInfo, Level};
+
+        let level = if self.rtl { Level::rtl() } else { Level::ltr() };
+        if !self.rtl && !BidiInfo::new(message, Some(level)).has_rtl() {
+            return Cow::Borrowed(message);
+        }
+        // Reorder line by line so newlines stay where they are.
+        let lines: Vec<String> = message
+            .split('\n')
+            .map(|line| {
+                let info = BidiInfo::new(line, Some(level));
+                match info.paragraphs.first() {
+                    Some(para) => info.reorder_line(para, para.range.clone()).into_owned(),
+                    None => String::new(),
+                }
+            })
+            .collect();
+        Cow::Owned(lines.join("\n"))
+    }
+}
+
+/// Decode ISO-8859-1 text. Every byte is the character with the same code,
+/// so 0xA0–0xFF land on the Latin-1 glyphs that FIGfonts define for those
+/// codes, for pipelines that don't speak UTF-8.
+pub fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// Run `filters` over `message` in order.
+pub fn apply_all<'a>(filters: &[Box<dyn InputFilter>], message: &'a str) -> Cow<'a, str> {
+    let mut message = Cow::Borrowed(message);
+    for filter in filters.iter() {
+        let next = match filter.apply(&message) {
+            Cow::Owned(next) => Some(next),
+            Cow::Borrowed(_) => None,
+        };
+        if let Some(next) = next {
+            message = Cow::Owned(next);
+        }
+    }
+    message
+}
+
+#[test]
+fn input_pipeline() {
+    let filters: Vec<Box<dyn InputFilter>> =
+        vec![Box::new(AsciiPunctuation), Box::new(Case::Upper)];
+    assert_eq!(apply_all(&filters, "\u{201c}hi\u{201d}"), "\"HI\"");
+    assert!(matches!(
+        apply_all(&[Box::new(AsciiPunctuation)], "plain"),
+        Cow::Borrowed("plain")
+    ));
+}
+
+#[test]
+fn latin1_bytes() {
+    assert_eq!(latin1(b"caf\xe9 \xa0\xff"), "caf\u{e9} \u{a0}\u{ff}");
+    let f = crate::font::Font::load_font("Standard.flf").unwrap();
+    assert_eq!(f.render(&latin1(b"\xc4")), f.render("\u{c4}"));
+}
+
+#[cfg(feature = "bidi")]
+#[test]
+fn bidi_reorder() {
+    let ltr = BidiReorder { rtl: false };
+    assert!(matches!(ltr.apply("plain"), Cow::Borrowed("plain")));
+    assert_eq!(
+        ltr.apply("a \u{5d0}\u{5d1}\u{5d2} b\nc"),
+        "a \u{5d2}\u{5d1}\u{5d0} b\nc"
+    );
+    let rtl = BidiReorder { rtl: true };
+    assert_eq!(rtl.apply("\u{5d0}\u{5d1} ab"), "ab \u{5d1}\u{5d0}");
+}
+
+#[test]
+fn control_chars() {
+    assert!(matches!(ControlChars.apply("a\nb"), Cow::Borrowed(_)));
+    assert_eq!(ControlChars.apply("hello\rj"), "jello");
+    assert_eq!(
+        ControlChars.apply("_\x08u_\x08sa\x08ag\x07e\tok"),
+        "usage ok"
+    );
+    assert_eq!(ControlChars.apply("ab\x08\x08\x08c\nd"), "cb\nd");
+}
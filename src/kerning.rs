@@ -0,0 +1,108 @@
+use crate::font::Font;
+use crate::glyph::Glyph;
+use crate::rules::Rules;
+
+/// Overlap in columns between every pair of printable ASCII glyphs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KerningTable {
+    pub pairs: Vec<(char, char, usize)>,
+}
+
+impl KerningTable {
+    pub fn overlap(&self, left: char, right: char) -> Option<usize> {
+        self.pairs
+            .iter()
+            .find(|(l, r, _)| *l == left && *r == right)
+            .map(|(_, _, overlap)| *overlap)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("left,right,overlap\n");
+        for (left, right, overlap) in self.pairs.iter() {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                csv_field(*left),
+                csv_field(*right),
+                overlap
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .pairs
+            .iter()
+            .map(|(left, right, overlap)| {
+                format!(
+                    "{{\"left\":{},\"right\":{},\"overlap\":{}}}",
+                    json_string(*left),
+                    json_string(*right),
+                    overlap
+                )
+            })
+            .collect();
+        format!("[{}]", entries.join(","))
+    }
+}
+
+fn csv_field(c: char) -> String {
+    match c {
+        '"' => String::from("\"\"\"\""),
+        ',' | ' ' => format!("\"{}\"", c),
+        _ => c.to_string(),
+    }
+}
+
+fn json_string(c: char) -> String {
+    match c {
+        '"' | '\\' => format!("\"\\{}\"", c),
+        _ => format!("\"{}\"", c),
+    }
+}
+
+impl Font {
+    pub fn kerning_table(&self) -> KerningTable {
+        self.kerning_table_with(&self.rules)
+    }
+
+    /// Like `kerning_table`, but with `rules` in place of the font's own.
+    pub fn kerning_table_with(&self, rules: &Rules) -> KerningTable {
+        let glyphs: Vec<(char, &Glyph)> = (32u8..=126)
+            .map(char::from)
+            .filter_map(|c| self.glyph(c).map(|g| (c, g)))
+            .collect();
+        let mut pairs = vec![];
+        for (left, lg) in glyphs.iter() {
+            for (right, rg) in glyphs.iter() {
+                let overlap = self.calc_overlay_with(rules, lg.rows(), rg) as usize;
+                pairs.push((*left, *right, overlap));
+            }
+        }
+        KerningTable { pairs }
+    }
+}
+
+#[test]
+fn kerning_table() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let table = f.kerning_table();
+    let (_, junctions) = f.render_debug("FI");
+    assert_eq!(table.overlap('F', 'I'), Some(junctions[0].overlap));
+    assert_eq!(
+        f.kerning_table_with(&Rules::default()).overlap('F', 'I'),
+        Some(0)
+    );
+
+    let table = KerningTable {
+        pairs: vec![('"', ',', 1), ('a', '\\', 0)],
+    };
+    assert_eq!(
+        table.to_csv(),
+        "left,right,overlap\n\"\"\"\",\",\",1\na,\\,0\n"
+    );
+    assert_eq!(
+        table.to_json(),
+        r#"[{"left":"\"","right":",","overlap":1},{"left":"a","right":"\\","overlap":0}]"#
+    );
+}
@@ -1,12 +1,53 @@
+use crate::error::LayoutError;
+use bitflags::bitflags;
 use std::str::FromStr;
+use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter};
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum LayoutType {
     Horizontal,
     Vertical,
 }
 
-#[derive(PartialEq, Eq, Debug)]
+bitflags! {
+    /// The layout bits of a font header's full layout field, one per
+    /// `SmushingRule`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct LayoutFlags: u32 {
+        const HORIZONTAL_EQUAL = 1;
+        const HORIZONTAL_UNDERSCORE = 2;
+        const HORIZONTAL_HIERARCHY = 4;
+        const HORIZONTAL_OPPOSITE_PAIR = 8;
+        const HORIZONTAL_BIG_X = 16;
+        const HORIZONTAL_HARDBLANK = 32;
+        const HORIZONTAL_FITTING = 64;
+        const HORIZONTAL_SMUSH = 128;
+        const VERTICAL_EQUAL = 256;
+        const VERTICAL_UNDERSCORE = 512;
+        const VERTICAL_HIERARCHY = 1024;
+        const VERTICAL_HORIZONTAL_LINE = 2048;
+        const VERTICAL_VERTICAL_LINE = 4096;
+        const VERTICAL_FITTING = 8192;
+        const VERTICAL_SMUSH = 16384;
+    }
+}
+
+impl LayoutFlags {
+    /// The flags an old layout field stands for: -1 for full width, 0 for
+    /// fitting, otherwise the horizontal smushing rules to use.
+    pub fn from_old_layout(old_layout: i32) -> LayoutFlags {
+        match old_layout {
+            0 => LayoutFlags::HORIZONTAL_FITTING,
+            layout if layout < 0 => LayoutFlags::empty(),
+            layout => {
+                LayoutFlags::from_bits_truncate(layout as u32) & LayoutFlags::from_bits_retain(63)
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
 pub enum LayoutMode {
     FullWidth,
     Fitting,
@@ -14,7 +55,24 @@ pub enum LayoutMode {
     UniversalSmush,
 }
 
-#[derive(EnumIter, Debug, Display, Clone, Copy, PartialEq, Eq)]
+impl FromStr for LayoutMode {
+    type Err = LayoutError;
+
+    /// Parse a mode by the name figlet's options use: "full", "fitting" or
+    /// "kern", "smush", and "universal".
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "full" | "full-width" => Ok(LayoutMode::FullWidth),
+            "fitting" | "fit" | "kern" | "kerning" => Ok(LayoutMode::Fitting),
+            "smush" | "smushing" | "controlled" => Ok(LayoutMode::ControlledSmush),
+            "universal" => Ok(LayoutMode::UniversalSmush),
+            _ => Err(LayoutError::UnknownMode(name.to_string())),
+        }
+    }
+}
+
+#[derive(EnumIter, Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SmushingRule {
     HorizontalEqualChar = 1,
     HorizontalUnderscore = 2,
@@ -33,6 +91,34 @@ pub enum SmushingRule {
     VerticalSmushing = 16384,
 }
 
+impl FromStr for SmushingRule {
+    type Err = LayoutError;
+
+    /// Parse a rule by its short name, e.g. "equal" or "vline", or by its
+    /// full variant name, e.g. "HorizontalBigX".
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let rule = match name.to_ascii_lowercase().as_str() {
+            "equal" => SmushingRule::HorizontalEqualChar,
+            "underscore" => SmushingRule::HorizontalUnderscore,
+            "hierarchy" => SmushingRule::HorizontalHierarchy,
+            "pair" => SmushingRule::HorizontalOppositePair,
+            "bigx" => SmushingRule::HorizontalBigX,
+            "hardblank" => SmushingRule::HorizontalHardblank,
+            "vequal" => SmushingRule::VerticalEqualChar,
+            "vunderscore" => SmushingRule::VerticalUnderscore,
+            "vhierarchy" => SmushingRule::VerticalHierarchy,
+            "hline" => SmushingRule::VerticalHorizontalLine,
+            "vline" => SmushingRule::VerticalVerticalLine,
+            _ => {
+                return SmushingRule::iter()
+                    .find(|rule| rule.to_string().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| LayoutError::UnknownRule(name.to_string()))
+            }
+        };
+        Ok(rule)
+    }
+}
+
 impl SmushingRule {
     pub fn smush(self, char1: char, char2: char, hardblank: char) -> Option<char> {
         match self {
@@ -57,9 +143,7 @@ impl SmushingRule {
                 let classes = "| /\\ [] {} () <>";
                 let pos1 = classes.find(char1);
                 let pos2 = classes.find(char2);
-                if pos1.is_some() && pos2.is_some() {
-                    let pos1 = pos1.unwrap();
-                    let pos2 = pos2.unwrap();
+                if let (Some(pos1), Some(pos2)) = (pos1, pos2) {
                     if pos1 != pos2 && (pos1 as i64 - pos2 as i64).abs() != 1 {
                         let max_pos = pos1.max(pos2);
                         return char::from_str(&classes[max_pos..=max_pos]).ok();
@@ -71,9 +155,7 @@ impl SmushingRule {
                 let brackets = "[] {} ()";
                 let pos1 = brackets.find(char1);
                 let pos2 = brackets.find(char2);
-                if pos1.is_some() && pos2.is_some() {
-                    let pos1 = pos1.unwrap();
-                    let pos2 = pos2.unwrap();
+                if let (Some(pos1), Some(pos2)) = (pos1, pos2) {
                     if (pos1 as i64 - pos2 as i64).abs() == 1 {
                         return Some('|');
                     }
@@ -106,16 +188,53 @@ impl SmushingRule {
                 }
             }
             SmushingRule::HorizontalSmushing => {
+                if char1 != hardblank && char2 != hardblank {
+                    Some(char2)
+                } else if cfg!(feature = "compat") {
+                    // figlet 2.2.5 smushes hardblanks too, keeping the
+                    // visible character.
+                    Some(if char2 == hardblank { char1 } else { char2 })
+                } else {
+                    None
+                }
+            }
+            // Here `char1` is above `char2`. The first three rules and the
+            // mode bits work as they do across.
+            SmushingRule::VerticalEqualChar => {
+                SmushingRule::HorizontalEqualChar.smush(char1, char2, hardblank)
+            }
+            SmushingRule::VerticalUnderscore => {
+                SmushingRule::HorizontalUnderscore.smush(char1, char2, hardblank)
+            }
+            SmushingRule::VerticalHierarchy => {
+                SmushingRule::HorizontalHierarchy.smush(char1, char2, hardblank)
+            }
+            SmushingRule::VerticalHorizontalLine => match (char1, char2) {
+                ('-', '_') | ('_', '-') => Some('='),
+                _ => None,
+            },
+            SmushingRule::VerticalVerticalLine => match (char1, char2) {
+                ('|', '|') => Some('|'),
+                _ => None,
+            },
+            SmushingRule::VerticalFitting => {
+                SmushingRule::HorizontalFitting.smush(char1, char2, hardblank)
+            }
+            SmushingRule::VerticalSmushing => {
                 if char1 != hardblank && char2 != hardblank {
                     Some(char2)
                 } else {
                     None
                 }
             }
-            _ => None,
         }
     }
 
+    /// This rule's bit in a full layout field.
+    pub fn flag(self) -> LayoutFlags {
+        LayoutFlags::from_bits_retain(self as u32)
+    }
+
     pub fn get_type(self) -> LayoutType {
         match self as isize {
             code if code <= 255 => LayoutType::Horizontal,
@@ -126,7 +245,7 @@ impl SmushingRule {
     pub fn get_mode(self) -> LayoutMode {
         match self as isize {
             code if code == 8192 || code == 64 => LayoutMode::Fitting,
-            code if code == 128 || code == 8192 => LayoutMode::UniversalSmush,
+            code if code == 128 || code == 16384 => LayoutMode::UniversalSmush,
             _ => LayoutMode::ControlledSmush,
         }
     }
@@ -189,3 +308,63 @@ fn test_horinaltal_hardblank() {
     assert_eq!(r.smush('$', '$', '$').unwrap(), '$');
     assert!(r.smush('a', 'b', '$').is_none());
 }
+
+#[test]
+fn test_vertical_rules() {
+    assert_eq!(
+        SmushingRule::VerticalHorizontalLine.smush('-', '_', '$'),
+        Some('=')
+    );
+    assert_eq!(
+        SmushingRule::VerticalHorizontalLine.smush('_', '-', '$'),
+        Some('=')
+    );
+    assert!(SmushingRule::VerticalHorizontalLine
+        .smush('-', '-', '$')
+        .is_none());
+    assert_eq!(
+        SmushingRule::VerticalVerticalLine.smush('|', '|', '$'),
+        Some('|')
+    );
+    assert!(SmushingRule::VerticalVerticalLine
+        .smush('|', '/', '$')
+        .is_none());
+    assert_eq!(
+        SmushingRule::VerticalUnderscore.smush('_', '/', '$'),
+        Some('/')
+    );
+    assert_eq!(
+        SmushingRule::VerticalSmushing.get_mode(),
+        LayoutMode::UniversalSmush
+    );
+}
+
+#[test]
+fn layout_flags() {
+    let flags = LayoutFlags::HORIZONTAL_EQUAL | LayoutFlags::HORIZONTAL_HIERARCHY;
+    assert_eq!(flags.bits(), 5);
+    assert_eq!(
+        SmushingRule::VerticalSmushing.flag(),
+        LayoutFlags::VERTICAL_SMUSH
+    );
+    assert_eq!(LayoutFlags::from_old_layout(-1), LayoutFlags::empty());
+    assert_eq!(
+        LayoutFlags::from_old_layout(0),
+        LayoutFlags::HORIZONTAL_FITTING
+    );
+    assert_eq!(LayoutFlags::from_old_layout(15).bits(), 15);
+}
+
+#[test]
+fn parse_names() {
+    assert_eq!("kern".parse(), Ok(LayoutMode::Fitting));
+    assert_eq!("Full".parse(), Ok(LayoutMode::FullWidth));
+    assert_eq!("universal".parse(), Ok(LayoutMode::UniversalSmush));
+    assert_eq!(
+        "tight".parse::<LayoutMode>(),
+        Err(LayoutError::UnknownMode("tight".to_string()))
+    );
+    assert_eq!("vline".parse(), Ok(SmushingRule::VerticalVerticalLine));
+    assert_eq!("horizontalbigx".parse(), Ok(SmushingRule::HorizontalBigX));
+    assert!("sideways".parse::<SmushingRule>().is_err());
+}
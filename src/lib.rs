@@ -1,3 +1,31 @@
+#[cfg(feature = "embedded-standard")]
+mod bundled;
+pub mod cache;
+pub mod canvas;
+pub mod color;
+pub mod debug;
+pub mod error;
+pub mod figure;
+pub mod filter;
 pub mod font;
+pub mod gallery;
+pub mod glyph;
+pub mod input;
+pub mod kerning;
 pub mod layout;
+pub mod lint;
+pub mod macros;
+pub mod markup;
+pub mod output;
+pub mod preview;
+pub mod render;
 pub mod rules;
+pub mod settings;
+mod shaping;
+pub mod splash;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "ttf")]
+pub mod ttf;
+pub mod typeset;
+pub mod wrap;
@@ -0,0 +1,342 @@
+use crate::font::{block_len, parse_code_tag, strip_row, Font, FontOpts, DEUTSCH_CODES};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    BadHeader,
+    GlyphHeight {
+        code: i32,
+        expected: usize,
+        found: usize,
+    },
+    RowTooLong {
+        code: i32,
+        length: usize,
+        max_length: usize,
+    },
+    InconsistentEndmark {
+        code: i32,
+        expected: char,
+        found: char,
+    },
+    MissingGlyph {
+        code: i32,
+    },
+    BadCodeTag,
+}
+
+/// A problem found in a font file, with the 1-based line it was found on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub line: usize,
+    pub kind: LintKind,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match self.kind {
+            LintKind::BadHeader => write!(f, "malformed header"),
+            LintKind::GlyphHeight {
+                code,
+                expected,
+                found,
+            } => write!(
+                f,
+                "glyph {} has {} rows, expected {}",
+                code, found, expected
+            ),
+            LintKind::RowTooLong {
+                code,
+                length,
+                max_length,
+            } => write!(
+                f,
+                "glyph {} row is {} long, max_length is {}",
+                code, length, max_length
+            ),
+            LintKind::InconsistentEndmark {
+                code,
+                expected,
+                found,
+            } => write!(
+                f,
+                "glyph {} row ends with {:?}, expected {:?}",
+                code, found, expected
+            ),
+            LintKind::MissingGlyph { code } => write!(f, "required glyph {} is missing", code),
+            LintKind::BadCodeTag => write!(f, "unreadable code tag"),
+        }
+    }
+}
+
+/// The rows of one glyph as found in the source, before any repair.
+struct Block<'a> {
+    code: i32,
+    line: usize,
+    rows: &'a [&'a str],
+}
+
+impl<'a> Block<'a> {
+    fn endmark(&self) -> char {
+        self.rows
+            .first()
+            .and_then(|row| row.trim_end().chars().last())
+            .unwrap_or('@')
+    }
+}
+
+struct Source<'a> {
+    header: FontOpts,
+    header_line: &'a str,
+    comments: &'a [&'a str],
+    blocks: Vec<Block<'a>>,
+    tags: Vec<(usize, &'a str)>,
+    missing: Vec<i32>,
+    issues: Vec<LintIssue>,
+}
+
+fn scan<'a>(lines: &'a [&'a str]) -> Option<Source<'a>> {
+    let header_line = *lines.first()?;
+    let header = match FontOpts::parse(header_line) {
+        Ok(header) if header_line.starts_with("flf2a") && header.height > 0 => header,
+        _ => return None,
+    };
+    let comments_end = (1 + header.comment_lines).min(lines.len());
+    let comments = &lines[1..comments_end];
+
+    let mut pos = comments_end;
+    let mut blocks = vec![];
+    let mut tags = vec![];
+    let mut missing = vec![];
+    let mut issues = vec![];
+
+    let required = (32..=126).chain(DEUTSCH_CODES.iter().cloned());
+    for code in required {
+        if pos >= lines.len() {
+            missing.push(code);
+            continue;
+        }
+        let len = block_len(&lines[pos..], header.height);
+        blocks.push(Block {
+            code,
+            line: pos + 1,
+            rows: &lines[pos..pos + len],
+        });
+        pos += len;
+    }
+    while pos < lines.len() {
+        if lines[pos].trim().is_empty() {
+            pos += 1;
+            continue;
+        }
+        let code = match parse_code_tag(lines[pos]) {
+            Some(code) => code,
+            None => {
+                issues.push(LintIssue {
+                    line: pos + 1,
+                    kind: LintKind::BadCodeTag,
+                });
+                0
+            }
+        };
+        tags.push((blocks.len(), lines[pos]));
+        pos += 1;
+        let len = block_len(&lines[pos..], header.height);
+        blocks.push(Block {
+            code,
+            line: pos + 1,
+            rows: &lines[pos..pos + len],
+        });
+        pos += len;
+    }
+
+    Some(Source {
+        header,
+        header_line,
+        comments,
+        blocks,
+        tags,
+        missing,
+        issues,
+    })
+}
+
+impl Font {
+    /// Check a font source for problems that keep it from parsing cleanly.
+    pub fn lint(data: &str) -> Vec<LintIssue> {
+        let lines: Vec<&str> = data.lines().collect();
+        let source = match scan(&lines) {
+            Some(source) => source,
+            None => {
+                return vec![LintIssue {
+                    line: 1,
+                    kind: LintKind::BadHeader,
+                }]
+            }
+        };
+        let header = &source.header;
+        let mut issues = source.issues;
+        for block in source.blocks.iter() {
+            if block.rows.len() != header.height {
+                issues.push(LintIssue {
+                    line: block.line,
+                    kind: LintKind::GlyphHeight {
+                        code: block.code,
+                        expected: header.height,
+                        found: block.rows.len(),
+                    },
+                });
+            }
+            let endmark = block.endmark();
+            for (i, row) in block.rows.iter().enumerate() {
+                let line = block.line + i;
+                let length = row.trim_end().chars().count();
+                if length > header.max_length {
+                    issues.push(LintIssue {
+                        line,
+                        kind: LintKind::RowTooLong {
+                            code: block.code,
+                            length,
+                            max_length: header.max_length,
+                        },
+                    });
+                }
+                let found = row.trim_end().chars().last().unwrap_or(' ');
+                if found != endmark {
+                    issues.push(LintIssue {
+                        line,
+                        kind: LintKind::InconsistentEndmark {
+                            code: block.code,
+                            expected: endmark,
+                            found,
+                        },
+                    });
+                }
+            }
+        }
+        let end = lines.len() + 1;
+        issues.extend(source.missing.iter().map(|&code| LintIssue {
+            line: end,
+            kind: LintKind::MissingGlyph { code },
+        }));
+        issues.sort_by_key(|issue| issue.line);
+        issues
+    }
+
+    /// Apply the safe fixes for `lint` issues: glyphs are padded or
+    /// truncated to the declared height, rows padded to the glyph width with
+    /// consistent endmarks, missing required glyphs added as empty ones and
+    /// max_length recomputed. Returns `None` if the header is unreadable.
+    pub fn repair(data: &str) -> Option<String> {
+        let lines: Vec<&str> = data.lines().collect();
+        let source = scan(&lines)?;
+        let height = source.header.height;
+
+        let mut glyphs = vec![];
+        let mut tags = source.tags.iter().peekable();
+        for (i, block) in source.blocks.iter().enumerate() {
+            if let Some((_, tag)) = tags.peek().filter(|(at, _)| *at == i) {
+                glyphs.push(tag.to_string());
+                tags.next();
+            }
+            let endmark = block.endmark();
+            let mut rows: Vec<&str> = block.rows.iter().map(|r| strip_row(r)).collect();
+            rows.resize(height, "");
+            let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+            for (r, row) in rows.iter().enumerate() {
+                let mut line = row.to_string();
+                line.extend(std::iter::repeat_n(' ', width - row.chars().count()));
+                line.push(endmark);
+                if r + 1 == height {
+                    line.push(endmark);
+                }
+                glyphs.push(line);
+            }
+        }
+        for _ in source.missing.iter() {
+            for r in 0..height {
+                glyphs.push(if r + 1 == height { "@@" } else { "@" }.to_string());
+            }
+        }
+
+        let max_length = glyphs.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut header: Vec<String> = source
+            .header_line
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        header[3] = max_length.to_string();
+
+        let mut out = header.join(" ");
+        for line in source.comments.iter().map(|l| l.to_string()).chain(glyphs) {
+            out.push('\n');
+            out.push_str(&line);
+        }
+        out.push('\n');
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+fn tiny_font(glyph: &str) -> String {
+    let mut data = String::from("flf2a$ 2 2 4 -1 1\ncomment\n");
+    for _ in 0..101 {
+        data.push_str("ab@\ncd@@\n");
+    }
+    data.push_str(glyph);
+    data
+}
+
+#[test]
+fn lint_clean_font() {
+    assert_eq!(Font::lint(&tiny_font("ab@\ncd@@\n")), vec![]);
+    let data = std::fs::read_to_string("fonts/Standard.flf").unwrap();
+    assert_eq!(Font::lint(&data), vec![]);
+}
+
+#[test]
+fn lint_problems() {
+    let issues = Font::lint(&tiny_font("abcd@@\n"));
+    let kinds: Vec<_> = issues.into_iter().map(|i| i.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            LintKind::GlyphHeight {
+                code: 223,
+                expected: 2,
+                found: 1
+            },
+            LintKind::RowTooLong {
+                code: 223,
+                length: 6,
+                max_length: 4
+            },
+        ]
+    );
+
+    let issues = Font::lint("flf2a$ 2 2 4 -1 0\nab@\ncd##\n");
+    assert_eq!(
+        issues[0].kind,
+        LintKind::InconsistentEndmark {
+            code: 32,
+            expected: '@',
+            found: '#'
+        }
+    );
+    assert_eq!(issues.len(), 102);
+    assert_eq!(issues[1].kind, LintKind::MissingGlyph { code: 33 });
+
+    assert_eq!(Font::lint("nonsense")[0].kind, LintKind::BadHeader);
+}
+
+#[test]
+fn repair() {
+    let fixed = Font::repair(&tiny_font("abcd@@\n")).unwrap();
+    assert_eq!(Font::lint(&fixed), vec![]);
+    assert!(fixed.starts_with("flf2a$ 2 2 6 -1 1\ncomment\n"));
+    assert!(fixed.ends_with("abcd@\n    @@\n"));
+
+    let fixed = Font::repair("flf2a$ 2 2 4 -1 0\nab@\ncd##\n").unwrap();
+    assert_eq!(Font::lint(&fixed), vec![]);
+}
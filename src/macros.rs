@@ -0,0 +1,44 @@
+/// The standard font built into the crate, parsed on first use. This is
+/// what `figlet!` and `figprintln!` render with when no font is given.
+#[cfg(feature = "embedded-standard")]
+#[doc(hidden)]
+pub fn standard_font() -> &'static crate::font::Font {
+    crate::font::Font::standard()
+}
+
+/// Render a `format!`-style message to a `String`, in the embedded standard
+/// font or in the font given first: `figlet!("v{}", 2)` or
+/// `figlet!(font, "Hi")`.
+#[macro_export]
+macro_rules! figlet {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::macros::standard_font().convert(&format!($fmt $(, $arg)*))
+    };
+    ($font:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        ($font).convert(&format!($fmt $(, $arg)*))
+    };
+}
+
+/// Like `figlet!`, but prints the banner followed by a newline, e.g.
+/// `figprintln!("Server started on port {}", port)`.
+#[macro_export]
+macro_rules! figprintln {
+    ($($args:tt)*) => {
+        println!("{}", $crate::figlet!($($args)*))
+    };
+}
+
+#[cfg(feature = "embedded-standard")]
+#[test]
+fn default_font_macros() {
+    let f = crate::font::Font::load_font("Standard.flf").unwrap();
+    assert_eq!(figlet!("Hi {}", 1), f.convert("Hi 1"));
+    assert_eq!(figlet!("Hi",), f.convert("Hi"));
+}
+
+#[test]
+fn font_macros() {
+    let slant = crate::font::Font::load_font("Slant.flf").unwrap();
+    assert_eq!(figlet!(slant, "Hi"), slant.convert("Hi"));
+    assert_eq!(figlet!(&slant, "{}!", "Hi"), slant.convert("Hi!"));
+}
@@ -1,7 +1,734 @@
-use figlet::font::Font;
+use figlet::color::{self, Color, ColorChoice, Style, Theme};
+use figlet::font::{Encoding, Font};
+use figlet::layout::{LayoutMode, SmushingRule};
+use figlet::markup;
+use figlet::output::{self, CommentStyle};
+use figlet::render::{self, Renderer};
+use figlet::rules::Rules;
+use figlet::splash;
+use std::convert::TryFrom;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const USAGE: &str =
+    "usage: figlet [-f FONT] [-t THEME] [--color WHEN] [-w WIDTH] [-l LAYOUT] [--vertical MODE] [--crlf] [--issue] [--comment STYLE] [--markup] [--latin1]
+              [--copy] [-0] [-q] [--pager WHEN] [-o FILE [--append]] [--json] [MESSAGE | @FILE | --file FILE]...
+       figlet lint FILE [--fix]
+       figlet gallery [SAMPLE...]
+       figlet inspect FONT
+       figlet repl";
+
+/// Set by `--json`, which makes errors and results JSON objects.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+fn usage() -> ! {
+    if JSON.load(Ordering::Relaxed) {
+        let error = json_object(&[
+            ("code", Json::String("usage")),
+            ("message", Json::String(USAGE)),
+        ]);
+        eprintln!("{{\"error\":{}}}", error);
+    } else {
+        eprintln!("{}", USAGE);
+    }
+    process::exit(2);
+}
+
+fn fail(message: &str) -> ! {
+    fail_with("error", message, &[])
+}
+
+/// Exit with `message`. With `--json` it's printed as an object along with
+/// a short `code` naming the kind of failure and `details` such as the
+/// font or path involved.
+fn fail_with(code: &str, message: &str, details: &[(&str, &str)]) -> ! {
+    let details: Vec<(&str, Json)> = details
+        .iter()
+        .map(|&(key, value)| (key, Json::String(value)))
+        .collect();
+    eprintln!("{}", diagnostic("error", code, message, &details));
+    process::exit(1);
+}
+
+/// An error or warning as printed: with `--json`, an object under `kind`
+/// with the code, message and details, and otherwise just the message.
+fn diagnostic(kind: &str, code: &str, message: &str, details: &[(&str, Json)]) -> String {
+    if JSON.load(Ordering::Relaxed) {
+        let mut fields = vec![
+            ("code", Json::String(code)),
+            ("message", Json::String(message)),
+        ];
+        fields.extend_from_slice(details);
+        format!("{{{}:{}}}", json_string(kind), json_object(&fields))
+    } else {
+        format!("figlet: {}", message)
+    }
+}
+
+/// With `--json`, warn about each character of `message` that `font`
+/// can't draw. Plain output stays as quiet about them as ever.
+fn warn_unsupported(font: &Font, message: &str) {
+    if !JSON.load(Ordering::Relaxed) {
+        return;
+    }
+    for (offset, c) in font.unsupported(message) {
+        let text = format!("{} has no glyph for {:?} at byte {}", font.name, c, offset);
+        let character = c.to_string();
+        let details = [
+            ("character", Json::String(&character)),
+            ("offset", Json::Number(offset)),
+            ("font", Json::String(&font.name)),
+        ];
+        eprintln!("{}", diagnostic("warning", "unsupported", &text, &details));
+    }
+}
+
+/// A value in a JSON object.
+#[derive(Debug, Clone, Copy)]
+enum Json<'a> {
+    String(&'a str),
+    Number(usize),
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_object(fields: &[(&str, Json)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|&(key, value)| match value {
+            Json::String(text) => format!("{}:{}", json_string(key), json_string(text)),
+            Json::Number(n) => format!("{}:{}", json_string(key), n),
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+fn load_font(name: &str) -> Font {
+    Font::load_font(name)
+        .unwrap_or_else(|e| fail_with("font", &format!("{}: {}", name, e), &[("font", name)]))
+}
 
 fn main() {
-    println!("Hello, world!");
-    let f = Font::load_font("4Max.flf");
-    dbg!(f);
+    let args: Vec<OsString> = env::args_os().skip(1).collect();
+    let latin1 = args.iter().any(|arg| arg == "--latin1");
+    JSON.store(args.iter().any(|arg| arg == "--json"), Ordering::Relaxed);
+    let args: Vec<String> = args.iter().map(|arg| decode_arg(arg, latin1)).collect();
+    match args.first().map(String::as_str) {
+        Some("lint") => lint(&args[1..]),
+        Some("gallery") => gallery(&args[1..]),
+        Some("inspect") => inspect(&args[1..]),
+        Some("repl") => repl(),
+        _ => render(&args),
+    }
+}
+
+/// An argument as text: its raw bytes read as ISO-8859-1 with `--latin1`,
+/// or else UTF-8.
+fn decode_arg(arg: &OsStr, latin1: bool) -> String {
+    #[cfg(unix)]
+    if latin1 {
+        use std::os::unix::ffi::OsStrExt;
+        return figlet::input::latin1(arg.as_bytes());
+    }
+    #[cfg(not(unix))]
+    let _ = latin1;
+    match arg.to_str() {
+        Some(arg) => arg.to_string(),
+        None => fail(&format!(
+            "{}: not valid UTF-8 (try --latin1)",
+            arg.to_string_lossy()
+        )),
+    }
+}
+
+fn render(args: &[String]) {
+    let mut font_name = String::from("Standard.flf");
+    let mut theme = None;
+    let mut width = None;
+    let mut layout: Option<Rules> = None;
+    let mut vertical: Option<LayoutMode> = None;
+    let mut crlf = false;
+    let mut issue = false;
+    let mut comment = None;
+    let mut markup = false;
+    let mut color_choice = ColorChoice::default();
+    let mut copy = false;
+    let mut output: Option<String> = None;
+    let mut append = false;
+    let mut quiet = false;
+    let mut null = false;
+    let mut latin1 = false;
+    let mut pager = Pager::Never;
+    let mut sources = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--font" => font_name = args.next().unwrap_or_else(|| usage()).clone(),
+            "-t" | "--theme" => theme = Some(load_theme(args.next().unwrap_or_else(|| usage()))),
+            "-w" | "--width" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                width = Some(value.parse().unwrap_or_else(|_| usage()));
+            }
+            "-l" | "--layout" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                layout = Some(value.parse().unwrap_or_else(|e| {
+                    fail_with("layout", &format!("{}", e), &[("layout", value)])
+                }));
+            }
+            "--vertical" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                vertical = Some(value.parse().unwrap_or_else(|e| {
+                    fail_with("layout", &format!("{}", e), &[("layout", value)])
+                }));
+            }
+            "--crlf" => crlf = true,
+            "--issue" => issue = true,
+            "--comment" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                comment = Some(CommentStyle::from_name(value).unwrap_or_else(|| {
+                    let message = format!("unknown comment style {}", value);
+                    fail_with("comment", &message, &[("style", value)])
+                }));
+            }
+            "--markup" => markup = true,
+            "--color" => color_choice = color_choice_named(args.next().unwrap_or_else(|| usage())),
+            arg if arg.starts_with("--color=") => color_choice = color_choice_named(&arg[8..]),
+            "--copy" => copy = true,
+            "-o" | "--output" => output = Some(args.next().unwrap_or_else(|| usage()).clone()),
+            "--append" => append = true,
+            "-q" | "--quiet" => quiet = true,
+            "--pager" => pager = pager_named(args.next().unwrap_or_else(|| usage())),
+            arg if arg.starts_with("--pager=") => pager = pager_named(&arg[8..]),
+            "-0" | "--null" => null = true,
+            "--file" => sources.push(Source::File(args.next().unwrap_or_else(|| usage()))),
+            "--latin1" => latin1 = true,
+            "--json" => {}
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            // `@@` escapes a message that starts with `@`.
+            arg if arg.starts_with("@@") => sources.push(Source::Word(&arg[1..])),
+            arg if arg.len() > 1 && arg.starts_with('@') => sources.push(Source::File(&arg[1..])),
+            _ => sources.push(Source::Word(arg)),
+        }
+    }
+    // With `-0`, the messages are read from stdin instead, each ended by a
+    // NUL, and every rendered block is ended by a NUL in turn.
+    let messages = match null {
+        true if sources.is_empty() => read_null_delimited(latin1),
+        false if !sources.is_empty() => read_sources(&sources, latin1),
+        _ => usage(),
+    };
+    let font = load_font(&font_name);
+    let mut renderer = Renderer::new(&font);
+    if let Some(width) = width {
+        renderer = renderer.width(width);
+    }
+    if let Some(layout) = layout {
+        renderer = renderer.rules(layout);
+    }
+    if let Some(vertical) = vertical {
+        renderer = renderer.vertical_layout(vertical);
+    }
+    let colored = markup || (theme.is_some() && comment.is_none() && !issue);
+    // Colors are dropped when not wanted, and on consoles that can't show
+    // them, such as on older Windows.
+    let keep = match color_choice {
+        ColorChoice::Auto => output.is_none() && color_choice.should_color(),
+        choice => choice.should_color(),
+    };
+    let keep = keep && (output.is_some() || color::enable_ansi());
+    let ending = match (null, crlf) {
+        (true, _) => "\0",
+        (false, true) => "\r\n",
+        (false, false) => "\n",
+    };
+    let line_ending = if crlf { "\r\n" } else { "\n" };
+    let blocks: Vec<String> = messages
+        .iter()
+        .map(|message| {
+            let text = if markup {
+                let spans = markup::parse(message)
+                    .unwrap_or_else(|e| fail_with("markup", &format!("{}", e), &[]));
+                let spans: Vec<(&str, Style)> = spans
+                    .iter()
+                    .map(|(text, style)| (text.as_str(), *style))
+                    .collect();
+                let plain: String = spans.iter().map(|(text, _)| *text).collect();
+                warn_unsupported(&font, &plain);
+                renderer.render_spans(&spans).paint()
+            } else {
+                warn_unsupported(&font, message);
+                let figure = renderer.render(message);
+                if let Some(style) = comment {
+                    output::comment(&figure, style)
+                } else if issue {
+                    output::issue(&figure)
+                } else if let Some(theme) = &theme {
+                    theme.paint(&figure)
+                } else {
+                    figure.to_string()
+                }
+            };
+            let text = if colored && !keep {
+                render::strip_ansi(&text).into_owned()
+            } else {
+                text
+            };
+            if JSON.load(Ordering::Relaxed) {
+                let rows: Vec<String> = text.split('\n').map(json_string).collect();
+                format!(
+                    "{{\"font\":{},\"rows\":[{}]}}",
+                    json_string(&font.name),
+                    rows.join(",")
+                )
+            } else {
+                text.replace('\n', line_ending)
+            }
+        })
+        .collect();
+    let text = blocks.join(ending);
+    if let Some(path) = &output {
+        let text = format!("{}{}", text, ending);
+        write_output(path, &text, append)
+            .unwrap_or_else(|e| fail_with("io", &format!("{}: {}", path, e), &[("path", path)]));
+    } else if append {
+        usage();
+    } else if !quiet {
+        page(&format!("{}{}", text, ending), pager);
+    }
+    if copy {
+        copy_to_clipboard(&text);
+    }
+}
+
+/// Where messages to render come from, in the order given.
+enum Source<'a> {
+    /// A word of a message; neighbouring words make up one message.
+    Word(&'a str),
+    /// A file with a message on each line, from `@FILE` or `--file FILE`.
+    File(&'a str),
+}
+
+/// The messages from `sources`: each run of words joined by spaces, and
+/// each non-blank line of each file.
+fn read_sources(sources: &[Source], latin1: bool) -> Vec<String> {
+    let mut messages = vec![];
+    let mut words = vec![];
+    for source in sources {
+        match *source {
+            Source::Word(word) => words.push(word),
+            Source::File(path) => {
+                if !words.is_empty() {
+                    messages.push(words.join(" "));
+                    words.clear();
+                }
+                let data = fs::read(path).unwrap_or_else(|e| {
+                    fail_with("io", &format!("{}: {}", path, e), &[("path", path)])
+                });
+                let text = match latin1 {
+                    true => figlet::input::latin1(&data),
+                    false => String::from_utf8(data).unwrap_or_else(|_| {
+                        fail(&format!("{}: not valid UTF-8 (try --latin1)", path))
+                    }),
+                };
+                messages.extend(
+                    text.lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .map(String::from),
+                );
+            }
+        }
+    }
+    if !words.is_empty() {
+        messages.push(words.join(" "));
+    }
+    messages
+}
+
+/// The NUL-delimited messages on stdin, as with `xargs -0`. A final NUL
+/// is optional.
+fn read_null_delimited(latin1: bool) -> Vec<String> {
+    let mut input = vec![];
+    io::stdin()
+        .lock()
+        .read_to_end(&mut input)
+        .unwrap_or_else(|e| fail_with("io", &format!("stdin: {}", e), &[]));
+    if input.last() == Some(&0) {
+        input.pop();
+    }
+    input
+        .split(|&b| b == 0)
+        .map(|message| match latin1 {
+            true => figlet::input::latin1(message),
+            false => String::from_utf8(message.to_vec())
+                .unwrap_or_else(|_| fail("stdin: not valid UTF-8 (try --latin1)")),
+        })
+        .collect()
+}
+
+/// When `--pager` sends output through `$PAGER`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pager {
+    /// Only when stdout is a terminal too short to show it all.
+    Auto,
+    Always,
+    Never,
+}
+
+fn pager_named(name: &str) -> Pager {
+    match name.to_ascii_lowercase().as_str() {
+        "auto" => Pager::Auto,
+        "always" | "yes" => Pager::Always,
+        "never" | "no" => Pager::Never,
+        _ => {
+            let message = format!(
+                "unknown pager choice {} (expected auto, always or never)",
+                name
+            );
+            fail_with("pager", &message, &[("pager", name)])
+        }
+    }
+}
+
+/// Print `text`, through the pager when `pager` calls for it. If the pager
+/// can't be started the text is printed directly.
+fn page(text: &str, pager: Pager) {
+    let wanted = match pager {
+        Pager::Always => true,
+        Pager::Never => false,
+        Pager::Auto => {
+            let (_, lines) = splash::terminal_size().unwrap_or(splash::DEFAULT_SIZE);
+            io::stdout().is_terminal() && text.lines().count() >= lines
+        }
+    };
+    if !(wanted && run_pager(text).is_ok()) {
+        print!("{}", text);
+    }
+}
+
+/// Show `text` in `$PAGER`, or `less` when that's unset, and wait for the
+/// user to leave it.
+fn run_pager(text: &str) -> io::Result<()> {
+    let command = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| String::from(if cfg!(windows) { "more" } else { "less" }));
+    let mut words = command.split_whitespace();
+    let mut pager = Command::new(words.next().unwrap_or_default());
+    pager.args(words).stdin(Stdio::piped());
+    // Let less pass colors through and quit at once when it all fits.
+    if env::var_os("LESS").is_none() {
+        pager.env("LESS", "FRX");
+    }
+    let mut child = pager.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Leaving the pager early closes the pipe, which is fine.
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+fn color_choice_named(name: &str) -> ColorChoice {
+    ColorChoice::from_name(name).unwrap_or_else(|| {
+        let message = format!(
+            "unknown color choice {} (expected auto, always or never)",
+            name
+        );
+        fail_with("color", &message, &[("color", name)])
+    })
+}
+
+/// Write `text` to `path`, or add it to the end with `append`. A new file
+/// is written next to the old one and renamed over it, so readers such as
+/// login banners never see it half written.
+fn write_output(path: &str, text: &str, append: bool) -> io::Result<()> {
+    if append {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        return file.write_all(text.as_bytes());
+    }
+    let path = Path::new(path);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, process::id()));
+    fs::write(&temp, text)?;
+    fs::rename(&temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .unwrap_or_else(|e| fail_with("clipboard", &format!("clipboard: {}", e), &[]));
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_: &str) {
+    fail_with(
+        "clipboard",
+        "built without clipboard support (enable the clipboard feature)",
+        &[],
+    );
+}
+
+/// A built-in theme by name, or with the `toml` feature a theme file.
+fn load_theme(name: &str) -> Theme {
+    if let Some(theme) = Theme::builtin(name) {
+        return theme;
+    }
+    #[cfg(feature = "toml")]
+    if name.ends_with(".toml") {
+        let data = fs::read_to_string(name)
+            .unwrap_or_else(|e| fail_with("io", &format!("{}: {}", name, e), &[("path", name)]));
+        return Theme::from_toml(&data).unwrap_or_else(|e| {
+            fail_with("theme", &format!("{}: {}", name, e), &[("theme", name)])
+        });
+    }
+    let names: Vec<&str> = Theme::builtin_names().collect();
+    let message = format!(
+        "unknown theme {} (expected one of {})",
+        name,
+        names.join(", ")
+    );
+    fail_with("theme", &message, &[("theme", name)])
+}
+
+const REPL_HELP: &str = ":font NAME     switch font
+:width N|off   wrap at N columns
+:color NAME    color with a theme or color name, or \"off\"
+:help          show this help
+:quit          leave";
+
+/// Render every line read from stdin, with `:` commands changing the font,
+/// width and color in between.
+fn repl() {
+    let mut font_name = String::from("Standard.flf");
+    let mut font = load_font(&font_name);
+    let mut width: Option<usize> = None;
+    let mut theme: Option<Theme> = None;
+    let ansi = ColorChoice::Auto.should_color() && color::enable_ansi();
+    let stdin = io::stdin();
+    loop {
+        eprint!("{}> ", font_name);
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(e) => fail(&format!("stdin: {}", e)),
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+        let Some(command) = line.strip_prefix(':') else {
+            let mut renderer = Renderer::new(&font);
+            if let Some(width) = width {
+                renderer = renderer.width(width);
+            }
+            let figure = renderer.render(line);
+            match &theme {
+                Some(theme) if ansi => println!("{}", theme.paint(&figure)),
+                _ => println!("{}", figure),
+            }
+            continue;
+        };
+        let (name, value) = command
+            .split_once(' ')
+            .map(|(name, value)| (name, value.trim()))
+            .unwrap_or((command, ""));
+        match name {
+            "font" | "f" => match Font::load_font(value) {
+                Ok(loaded) => {
+                    font_name = loaded.name.clone();
+                    font = loaded;
+                }
+                Err(e) => eprintln!("{}: {}", value, e),
+            },
+            "width" | "w" => match value {
+                "off" => width = None,
+                _ => match value.parse() {
+                    Ok(columns) => width = Some(columns),
+                    Err(_) => eprintln!("not a width: {}", value),
+                },
+            },
+            "color" | "c" => {
+                let named = Color::from_name(value).or_else(|| Color::from_hex(value));
+                match (value, named) {
+                    ("off", _) => theme = None,
+                    (_, Some(color)) => theme = Some(Theme::new(value, vec![color])),
+                    _ => match Theme::builtin(value) {
+                        Some(builtin) => theme = Some(builtin),
+                        None => eprintln!("unknown color {}", value),
+                    },
+                }
+            }
+            "help" | "h" => eprintln!("{}", REPL_HELP),
+            "quit" | "q" => return,
+            _ => eprintln!("unknown command :{} (try :help)", name),
+        }
+    }
+}
+
+/// Print what a font's header says and how it's laid out.
+fn inspect(args: &[String]) {
+    let [name] = args else { usage() };
+    let font = load_font(name);
+    let head = &font.font_head;
+    let rules = font.layout();
+    let list = |rules: &[SmushingRule]| match rules {
+        [] => String::from("none"),
+        _ => rules
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    println!("font: {}", name);
+    println!("hardblank: {}", head.hardblank());
+    println!("height: {}", head.height());
+    println!("baseline: {}", head.baseline());
+    println!("max length: {}", head.max_length());
+    println!("old layout: {}", head.old_layout());
+    match head.full_layout() {
+        Some(flags) => println!("full layout: {} {:?}", flags.bits(), flags),
+        None => println!("full layout: none"),
+    }
+    println!(
+        "print direction: {}",
+        match head.print_direction() {
+            1 => "right to left",
+            _ => "left to right",
+        }
+    );
+    println!("comment lines: {}", head.comment_lines());
+    match head.codetag_count() {
+        Some(count) => println!("code tags: {}", count),
+        None => println!("code tags: none"),
+    }
+    println!("horizontal layout: {:?}", rules.horizontal_layout);
+    println!("horizontal rules: {}", list(&rules.horizontal_rules));
+    println!("vertical layout: {:?}", rules.vertical_layout);
+    println!("vertical rules: {}", list(&rules.vertical_rules));
+    println!("glyphs: {}", font.glyph_count());
+    println!("coverage: {}", coverage_ranges(&font));
+    println!("comment:");
+    for line in font.meta_data.lines() {
+        println!("  {}", line);
+    }
+}
+
+/// The characters a font covers as runs like `U+0020-U+007E`.
+fn coverage_ranges(font: &Font) -> String {
+    let mut runs: Vec<(char, char)> = vec![];
+    for c in font.coverage() {
+        match runs.last_mut() {
+            Some((_, end)) if *end as u32 + 1 == c as u32 => *end = c,
+            _ => runs.push((c, c)),
+        }
+    }
+    let runs: Vec<String> = runs
+        .iter()
+        .map(|&(start, end)| match start == end {
+            true => format!("U+{:04X}", start as u32),
+            false => format!("U+{:04X}-U+{:04X}", start as u32, end as u32),
+        })
+        .collect();
+    runs.join(", ")
+}
+
+/// Print an HTML page showing every installed font that loads.
+fn gallery(args: &[String]) {
+    let sample = if args.is_empty() {
+        String::from("Hello")
+    } else {
+        args.join(" ")
+    };
+    let names = Font::installed().unwrap_or_else(|e| fail(&format!("fonts: {}", e)));
+    let fonts: Vec<Font> = names
+        .iter()
+        .filter_map(|name| Font::load_font(name).ok())
+        .collect();
+    let fonts: Vec<&Font> = fonts.iter().collect();
+    print!("{}", figlet::gallery::gallery(&fonts, &sample));
+}
+
+/// The text of the font file at `path`, decoded as `Font::load_font`
+/// would, and whether it was UTF-8 rather than Latin-1.
+fn read_font_file(path: &str) -> (String, bool) {
+    let data = fs::read(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+    let utf8 = std::str::from_utf8(&data).is_ok();
+    let text = Encoding::Auto
+        .decode(&data)
+        .unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+    (text.into_owned(), utf8)
+}
+
+fn lint(args: &[String]) {
+    let mut fix = false;
+    let mut file = None;
+    for arg in args {
+        match arg.as_str() {
+            "--fix" => fix = true,
+            _ if file.is_none() => file = Some(arg.as_str()),
+            _ => usage(),
+        }
+    }
+    let file = file.unwrap_or_else(|| usage());
+    let (data, utf8) = read_font_file(file);
+
+    let issues = Font::lint(&data);
+    for issue in issues.iter() {
+        println!("{}: {}", file, issue);
+    }
+    if issues.is_empty() {
+        return;
+    }
+    if !fix {
+        process::exit(1);
+    }
+    let fixed = Font::repair(&data)
+        .unwrap_or_else(|| fail(&format!("{}: header is unreadable, not repaired", file)));
+    // Latin-1 fonts are written back as Latin-1.
+    let fixed = match utf8 {
+        true => fixed.into_bytes(),
+        false => fixed
+            .chars()
+            .map(|c| u8::try_from(c).ok())
+            .collect::<Option<Vec<u8>>>()
+            .unwrap_or_else(|| fail(&format!("{}: repair is not Latin-1, not written", file))),
+    };
+    fs::write(file, fixed).unwrap_or_else(|e| fail(&format!("{}: {}", file, e)));
+    let remaining = Font::lint(&read_font_file(file).0).len();
+    println!(
+        "{}: fixed {} issues, {} remaining",
+        file,
+        issues.len() - remaining.min(issues.len()),
+        remaining
+    );
+    if remaining > 0 {
+        process::exit(1);
+    }
 }
@@ -0,0 +1,123 @@
+use crate::color::{Color, Style};
+use crate::error::MarkupError;
+
+/// Split text with inline style tags into spans for
+/// `Renderer::render_spans`, e.g. `{red}ERROR{/red} in {bold}core{/bold}`.
+///
+/// A tag is `bold`, a color name or `#rrggbb` for the foreground, or the
+/// same prefixed with `bg:` for the background. Tags nest, `{/name}` closes
+/// the innermost open tag of that name and `{/}` whichever is innermost.
+/// Tags still open at the end simply run to the end, and `{{` is a literal
+/// brace.
+pub fn parse(text: &str) -> Result<Vec<(String, Style)>, MarkupError> {
+    let mut spans: Vec<(String, Style)> = vec![];
+    let mut open: Vec<(&str, Style)> = vec![];
+    let mut current = String::new();
+    let mut rest = text;
+    while let Some(brace) = rest.find('{') {
+        current.push_str(&rest[..brace]);
+        let offset = text.len() - rest.len() + brace;
+        rest = &rest[brace + 1..];
+        if let Some(after) = rest.strip_prefix('{') {
+            current.push('{');
+            rest = after;
+            continue;
+        }
+        let end = rest.find('}').ok_or(MarkupError::Unterminated { offset })?;
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        let style = open.last().map(|(_, style)| *style).unwrap_or_default();
+        push(&mut spans, &mut current, style);
+        match tag.strip_prefix('/') {
+            Some("") if !open.is_empty() => {
+                open.pop();
+            }
+            Some(name) => match open.iter().rposition(|(open, _)| *open == name) {
+                Some(i) => open.truncate(i),
+                None => {
+                    return Err(MarkupError::UnmatchedClose {
+                        tag: name.to_string(),
+                        offset,
+                    })
+                }
+            },
+            None => {
+                let style = apply(tag, style).ok_or_else(|| MarkupError::UnknownTag {
+                    tag: tag.to_string(),
+                    offset,
+                })?;
+                open.push((tag, style));
+            }
+        }
+    }
+    current.push_str(rest);
+    let style = open.last().map(|(_, style)| *style).unwrap_or_default();
+    push(&mut spans, &mut current, style);
+    Ok(spans)
+}
+
+/// `style` with `tag` applied on top.
+fn apply(tag: &str, mut style: Style) -> Option<Style> {
+    let color = |name: &str| Color::from_name(name).or_else(|| Color::from_hex(name));
+    if tag == "bold" {
+        style.bold = true;
+    } else if let Some(name) = tag.strip_prefix("bg:") {
+        style.bg = Some(color(name)?);
+    } else {
+        style.fg = Some(color(tag)?);
+    }
+    Some(style)
+}
+
+/// End the span collected in `current`, merging it into the last one when
+/// the style is the same.
+fn push(spans: &mut Vec<(String, Style)>, current: &mut String, style: Style) {
+    if current.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some((text, last)) if *last == style => text.push_str(current),
+        _ => spans.push((current.clone(), style)),
+    }
+    current.clear();
+}
+
+#[test]
+fn parse_markup() {
+    let red = Style::fg(Color::from_name("red").unwrap());
+    let bold = Style {
+        bold: true,
+        ..Style::default()
+    };
+    assert_eq!(
+        parse("{red}ERROR{/red} in {bold}core{/bold}").unwrap(),
+        vec![
+            ("ERROR".to_string(), red),
+            (" in ".to_string(), Style::default()),
+            ("core".to_string(), bold),
+        ]
+    );
+    let nested = parse("{bold}a{#00ff00}b{/}c{bg:blue}d").unwrap();
+    assert_eq!(nested[0], ("a".to_string(), bold));
+    assert_eq!(nested[1].1.fg, Some(Color::rgb(0, 0xff, 0)));
+    assert!(nested[1].1.bold);
+    assert_eq!(nested[2], ("c".to_string(), bold));
+    assert_eq!(nested[3].1.bg, Color::from_name("blue"));
+    assert_eq!(
+        parse("{{x} {}").unwrap_err(),
+        MarkupError::UnknownTag {
+            tag: String::new(),
+            offset: 5
+        }
+    );
+    assert_eq!(
+        parse("{{x}").unwrap(),
+        vec![("{x}".to_string(), Style::default())]
+    );
+    assert!(matches!(
+        parse("a{/red}"),
+        Err(MarkupError::UnmatchedClose { offset: 1, .. })
+    ));
+    assert_eq!(parse("{red"), Err(MarkupError::Unterminated { offset: 0 }));
+}
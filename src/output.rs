@@ -0,0 +1,122 @@
+use crate::figure::FIGure;
+
+/// What `issue` draws for strokes outside printable ASCII.
+pub const ISSUE_FALLBACK: char = '#';
+
+/// The figure as text safe to write into `/etc/issue`. agetty reads `\`
+/// and `@` as the start of escapes, so both are escaped with a backslash,
+/// and anything outside printable ASCII, which the console may not be able
+/// to show, becomes `ISSUE_FALLBACK`.
+pub fn issue(figure: &FIGure) -> String {
+    let mut text = String::new();
+    for (i, row) in figure.rows().iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        for &c in row {
+            match c {
+                '\\' | '@' => {
+                    text.push('\\');
+                    text.push(c);
+                }
+                ' '..='~' => text.push(c),
+                _ => text.push(ISSUE_FALLBACK),
+            }
+        }
+    }
+    text
+}
+
+/// Comment syntax for `comment`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// `// ` before every row, as in C++, Rust and JavaScript.
+    Slash,
+    /// `# ` before every row, as in shell, Python and TOML.
+    Hash,
+    /// `-- ` before every row, as in SQL, Lua and Haskell.
+    Dash,
+    /// A `/* */` block with ` * ` before every row.
+    Block,
+    /// An HTML or XML `<!-- -->` block.
+    Html,
+}
+
+impl CommentStyle {
+    /// The style for a name such as "rust", "python" or "html", or for
+    /// the comment marker itself, e.g. "#".
+    pub fn from_name(name: &str) -> Option<CommentStyle> {
+        let style = match name.to_ascii_lowercase().as_str() {
+            "//" | "slash" | "c++" | "cpp" | "rust" | "rs" | "js" | "go" => CommentStyle::Slash,
+            "#" | "hash" | "sh" | "shell" | "python" | "py" | "toml" | "yaml" => CommentStyle::Hash,
+            "--" | "dash" | "sql" | "lua" | "haskell" | "hs" => CommentStyle::Dash,
+            "/*" | "block" | "c" | "css" | "java" => CommentStyle::Block,
+            "<!--" | "html" | "xml" | "markdown" | "md" => CommentStyle::Html,
+            _ => return None,
+        };
+        Some(style)
+    }
+}
+
+/// The figure as a source comment, e.g. for a section header. Every row
+/// keeps its indentation, but trailing spaces are dropped. Strokes that
+/// would end a block comment early, `*/` or `--` in HTML, have their
+/// second character swapped for `|` or `~`, so widths don't change.
+pub fn comment(figure: &FIGure, style: CommentStyle) -> String {
+    let prefix = match style {
+        CommentStyle::Slash => "// ",
+        CommentStyle::Hash => "# ",
+        CommentStyle::Dash => "-- ",
+        CommentStyle::Block => " * ",
+        CommentStyle::Html => "",
+    };
+    let mut lines: Vec<String> = figure
+        .iter()
+        .map(|row| {
+            let row = match style {
+                CommentStyle::Block => row.replace("*/", "*|"),
+                CommentStyle::Html => row.replace("--", "-~"),
+                _ => row.to_string(),
+            };
+            format!("{}{}", prefix, row).trim_end().to_string()
+        })
+        .collect();
+    match style {
+        CommentStyle::Block => {
+            lines.insert(0, "/*".to_string());
+            lines.push(" */".to_string());
+        }
+        CommentStyle::Html => {
+            lines.insert(0, "<!--".to_string());
+            lines.push("-->".to_string());
+        }
+        _ => {}
+    }
+    lines.join("\n")
+}
+
+#[test]
+fn issue_escapes() {
+    let figure = FIGure::new(vec![
+        "/\\ @".chars().collect(),
+        "\u{2588}\t|".chars().collect(),
+    ]);
+    assert_eq!(issue(&figure), "/\\\\ \\@\n##|");
+}
+
+#[test]
+fn comments() {
+    let figure = FIGure::new(vec![" _  ".chars().collect(), "|_| ".chars().collect()]);
+    assert_eq!(comment(&figure, CommentStyle::Slash), "//  _\n// |_|");
+    assert_eq!(
+        comment(&figure, CommentStyle::Block),
+        "/*\n *  _\n * |_|\n */"
+    );
+    assert_eq!(comment(&figure, CommentStyle::Html), "<!--\n _\n|_|\n-->");
+    let closing = FIGure::new(vec!["*/ ---".chars().collect()]);
+    assert_eq!(comment(&closing, CommentStyle::Block), "/*\n * *| ---\n */");
+    assert_eq!(comment(&closing, CommentStyle::Html), "<!--\n*/ -~-\n-->");
+    assert_eq!(CommentStyle::from_name("Python"), Some(CommentStyle::Hash));
+    assert_eq!(CommentStyle::from_name("--"), Some(CommentStyle::Dash));
+    assert_eq!(CommentStyle::from_name("cobol"), None);
+}
@@ -0,0 +1,92 @@
+use crate::canvas::Canvas;
+use crate::figure::FIGure;
+use crate::font::Font;
+use std::path::Path;
+
+/// How `preview` arranges the samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewLayout {
+    /// Side by side, `gap` columns apart.
+    Columns { gap: usize },
+    /// One under another, with a row of `separator` between them.
+    Stacked { separator: char },
+}
+
+impl Default for PreviewLayout {
+    fn default() -> Self {
+        PreviewLayout::Stacked { separator: '-' }
+    }
+}
+
+/// `sample` rendered in each of `fonts`, labeled with the font's name and
+/// arranged as `layout` says, for font pickers and documentation.
+pub fn preview(fonts: &[&Font], sample: &str, layout: PreviewLayout) -> FIGure {
+    let blocks: Vec<FIGure> = fonts
+        .iter()
+        .map(|font| {
+            let mut block = FIGure::new(vec![label(font).chars().collect()]);
+            block.overlay(&font.render(sample), 0, 1);
+            block
+        })
+        .collect();
+    match layout {
+        PreviewLayout::Columns { gap } => {
+            let width = blocks.iter().map(|b| b.width() + gap).sum::<usize>();
+            let height = blocks.iter().map(FIGure::height).max().unwrap_or(0);
+            let mut canvas = Canvas::new(width.saturating_sub(gap), height);
+            let mut x = 0;
+            for block in blocks.iter() {
+                canvas.blit(block, x, 0);
+                x += block.width() + gap;
+            }
+            canvas.into_figure()
+        }
+        PreviewLayout::Stacked { separator } => {
+            let width = blocks.iter().map(FIGure::width).max().unwrap_or(0);
+            let mut rows = vec![];
+            for (i, block) in blocks.iter().enumerate() {
+                if i > 0 {
+                    rows.push(vec![separator; width]);
+                }
+                rows.extend(block.rows().iter().cloned());
+            }
+            FIGure::new(rows)
+        }
+    }
+}
+
+/// The font's file name without its extension.
+fn label(font: &Font) -> &str {
+    Path::new(&font.name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(&font.name)
+}
+
+#[test]
+fn preview_fonts() {
+    let standard = Font::load_font("Standard.flf").unwrap();
+    let small = Font::load_font("Small.flf").unwrap();
+    let fonts = [&standard, &small];
+    let stacked = preview(&fonts, "Hi", PreviewLayout::default());
+    let rows: Vec<&str> = stacked.iter().map(str::trim_end).collect();
+    assert_eq!(rows[0], "Standard");
+    let hi: Vec<String> = standard
+        .convert("Hi")
+        .lines()
+        .map(|row| row.trim_end().to_string())
+        .collect();
+    assert_eq!(rows[1..7], hi[..]);
+    assert_eq!(rows[7], "-".repeat(stacked.width()));
+    assert_eq!(rows[8], "Small");
+    assert_eq!(stacked.height(), 1 + 6 + 1 + 1 + 5);
+
+    let columns = preview(&fonts, "Hi", PreviewLayout::Columns { gap: 2 });
+    assert!(columns.iter().next().unwrap().starts_with("Standard  "));
+    assert_eq!(columns.height(), 7);
+    let block = |font: &Font, name: &str| font.render("Hi").width().max(name.len());
+    assert_eq!(
+        columns.width(),
+        block(&standard, "Standard") + 2 + block(&small, "Small")
+    );
+}
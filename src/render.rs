@@ -0,0 +1,1386 @@
+use crate::color::{Style, StyledFIGure};
+use crate::error::RenderError;
+use crate::figure::{self, FIGure};
+use crate::filter::Filter;
+use crate::font::{Compose, Font};
+use crate::input::{self, AsciiPunctuation, ControlChars, InputFilter};
+use crate::layout::LayoutMode;
+use crate::rules::Rules;
+use crate::shaping;
+use crate::wrap::{self, WrapPolicy};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "async")]
+use std::future::{self, Future};
+use std::io;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "async")]
+use std::task::Poll;
+
+#[cfg(feature = "async")]
+use futures_io::AsyncWrite;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+}
+
+impl Case {
+    pub fn apply(self, message: &str) -> String {
+        match self {
+            Case::Upper => message.to_uppercase(),
+            Case::Lower => message.to_lowercase(),
+        }
+    }
+}
+
+/// Replace typographic quotes, dashes and ellipses with their ASCII
+/// equivalents, which nearly every font covers.
+pub fn ascii_punctuation(message: &str) -> String {
+    let mut result = String::with_capacity(message.len());
+    for c in message.chars() {
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' | '\u{2032}' => result.push('\''),
+            '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{201f}' | '\u{2033}' => result.push('"'),
+            '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2212}' => result.push('-'),
+            '\u{2014}' | '\u{2015}' => result.push_str("--"),
+            '\u{2026}' => result.push_str("..."),
+            '\u{00a0}' => result.push(' '),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[test]
+fn test_ascii_punctuation() {
+    assert_eq!(
+        ascii_punctuation("\u{201c}Wait\u{2026}\u{201d} \u{2018}1\u{2013}2\u{2019} \u{2014}"),
+        "\"Wait...\" '1-2' --"
+    );
+    assert_eq!(ascii_punctuation("plain"), "plain");
+}
+
+/// What the renderer does with ANSI escape sequences in its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnsiPolicy {
+    /// Remove them, so colored command output renders as plain text.
+    #[default]
+    Strip,
+    /// Make `try_render` fail on them.
+    Reject,
+    /// Leave them in and look them up as glyphs like any other input.
+    Keep,
+}
+
+/// Length in bytes of the escape sequence at the start of `s`, if any.
+fn escape_len(s: &str) -> Option<usize> {
+    let mut chars = s.char_indices();
+    if chars.next()?.1 != '\x1b' {
+        return None;
+    }
+    match chars.next() {
+        // CSI: parameter and intermediate bytes, then one final byte.
+        Some((_, '[')) => chars
+            .find(|(_, c)| ('\x40'..='\x7e').contains(c))
+            .map(|(i, c)| i + c.len_utf8())
+            .or(Some(s.len())),
+        // OSC: runs until BEL or ESC \.
+        Some((_, ']')) => {
+            let mut prev = ' ';
+            for (i, c) in chars {
+                if c == '\x07' || (prev == '\x1b' && c == '\\') {
+                    return Some(i + 1);
+                }
+                prev = c;
+            }
+            Some(s.len())
+        }
+        // Other escapes: intermediate bytes, then one final byte.
+        Some((_, c)) if (' '..='/').contains(&c) => chars
+            .find(|(_, c)| !(' '..='/').contains(c))
+            .map(|(i, c)| i + c.len_utf8())
+            .or(Some(s.len())),
+        Some((i, c)) => Some(i + c.len_utf8()),
+        None => Some(1),
+    }
+}
+
+/// Byte offset of the first ANSI escape sequence in `message`.
+pub fn find_ansi(message: &str) -> Option<usize> {
+    message.find('\x1b')
+}
+
+/// Remove ANSI escape sequences such as color codes from `message`.
+pub fn strip_ansi(message: &str) -> Cow<'_, str> {
+    let mut start = match find_ansi(message) {
+        Some(start) => start,
+        None => return Cow::Borrowed(message),
+    };
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+    loop {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        rest = &rest[escape_len(rest).unwrap_or(1)..];
+        match find_ansi(rest) {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+    result.push_str(rest);
+    Cow::Owned(result)
+}
+
+#[test]
+fn ansi_escapes() {
+    assert_eq!(strip_ansi("plain"), Cow::Borrowed("plain"));
+    assert_eq!(strip_ansi("\x1b[1;31mred\x1b[0m!"), "red!");
+    assert_eq!(strip_ansi("\x1b]0;title\x07a\x1b]8;;x\x1b\\b"), "ab");
+    assert_eq!(strip_ansi("a\x1b(Bb\x1b[3"), "ab");
+    assert_eq!(find_ansi("ok\x1b[0m"), Some(2));
+}
+
+/// What to draw for characters the font has no glyph for, such as emoji.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingPolicy {
+    /// The font's missing-character glyph, code 0, if it has one;
+    /// otherwise nothing, as figlet does.
+    #[default]
+    Default,
+    /// Nothing at all.
+    Skip,
+    /// The glyph for another character, e.g. `?`.
+    Placeholder(char),
+    /// Emoji as their shortcode text, e.g. `:rocket:`. Anything else is
+    /// handled as by `Default`.
+    #[cfg(feature = "emoji")]
+    Shortcode,
+}
+
+/// Which way characters are laid out on a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+    /// Right to left when the first letter of the input is Hebrew, Arabic
+    /// or another right-to-left script.
+    Auto,
+}
+
+/// Where lines narrower than the output sit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// A flag to stop renders that are taking too long, shared between the
+/// thread rendering and whoever decides to give up on it. Renders check it
+/// before laying out each glyph and each line, and stop there.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    /// Stop every render using this token, now and from now on.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Caps on how big a render may get, for services rendering untrusted
+/// input. Rendering stops as soon as one is passed, before the rest of the
+/// output is laid out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    width: Option<usize>,
+    height: Option<usize>,
+    cells: Option<usize>,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Limits::default()
+    }
+
+    /// The most columns output may have.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// The most rows output may have.
+    pub fn height(mut self, height: usize) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// The most cells output may have, counting its width times height.
+    pub fn cells(mut self, cells: usize) -> Self {
+        self.cells = Some(cells);
+        self
+    }
+
+    /// Whether output `width` by `height` is within the limits.
+    pub fn allow(&self, width: usize, height: usize) -> bool {
+        self.width.is_none_or(|max| width <= max)
+            && self.height.is_none_or(|max| height <= max)
+            && self
+                .cells
+                .is_none_or(|max| width.saturating_mul(height) <= max)
+    }
+
+    /// The widest a line `height` rows tall can be.
+    fn line_width(&self, height: usize) -> Option<usize> {
+        let cells = self.cells.map(|cells| cells / height.max(1));
+        match (self.width, cells) {
+            (Some(width), Some(cells)) => Some(width.min(cells)),
+            (width, cells) => width.or(cells),
+        }
+    }
+}
+
+/// Lines laid out during `Renderer::render_batch`, by text and direction.
+type LineCache = RefCell<HashMap<(String, bool), FIGure>>;
+
+/// How many lines `render_batch` remembers before starting over, so a
+/// long-running batch of distinct messages doesn't grow without bound.
+const BATCH_CACHE_LINES: usize = 4096;
+
+/// A render in progress: the prepared message's paragraphs, laid out one
+/// at a time, and the lines laid out so far.
+struct Layout {
+    paragraphs: std::vec::IntoIter<String>,
+    rtl: bool,
+    /// Whether glyphs go right to left.
+    reverse: bool,
+    width: Option<usize>,
+    rules: Rules,
+    lines: Vec<FIGure>,
+}
+
+#[derive(Debug)]
+pub struct Renderer<'a> {
+    font: &'a Font,
+    inputs: Vec<Box<dyn InputFilter>>,
+    pad_to_rect: bool,
+    raw: bool,
+    background: Option<char>,
+    filters: Vec<Box<dyn Filter>>,
+    substitutions: HashMap<char, char>,
+    kerning: HashMap<(char, char), isize>,
+    ligatures: Vec<(String, i32)>,
+    missing: MissingPolicy,
+    ansi: AnsiPolicy,
+    bidi: bool,
+    direction: Option<Direction>,
+    align: Option<Align>,
+    width: Option<usize>,
+    wrap_policy: WrapPolicy,
+    hyphenate: bool,
+    rules: Option<Rules>,
+    vertical: Option<LayoutMode>,
+    line_ending: String,
+    cancel: Option<CancelToken>,
+    limits: Limits,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(font: &'a Font) -> Self {
+        Renderer {
+            font,
+            inputs: vec![],
+            pad_to_rect: false,
+            raw: false,
+            background: None,
+            filters: vec![],
+            substitutions: HashMap::new(),
+            kerning: HashMap::new(),
+            ligatures: vec![],
+            missing: MissingPolicy::default(),
+            ansi: AnsiPolicy::default(),
+            bidi: false,
+            direction: None,
+            align: None,
+            width: None,
+            wrap_policy: WrapPolicy::default(),
+            hyphenate: false,
+            rules: None,
+            vertical: None,
+            line_ending: String::from("\n"),
+            cancel: None,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Add an input filter; input filters run in the order they were
+    /// added, after ANSI escapes are handled.
+    pub fn input<F: InputFilter + 'static>(mut self, filter: F) -> Self {
+        self.inputs.push(Box::new(filter));
+        self
+    }
+
+    /// Force the input to one case before glyph lookup, for fonts that
+    /// only define upper or lower case letters.
+    pub fn case(self, case: Case) -> Self {
+        self.input(case)
+    }
+
+    pub fn ascii_punctuation(self, enabled: bool) -> Self {
+        if enabled {
+            self.input(AsciiPunctuation)
+        } else {
+            self
+        }
+    }
+
+    /// Pad every output row to the width of the widest one.
+    pub fn pad_to_rect(mut self, enabled: bool) -> Self {
+        self.pad_to_rect = enabled;
+        self
+    }
+
+    /// Keep hardblanks in the output instead of turning them into spaces.
+    pub fn raw(mut self, enabled: bool) -> Self {
+        self.raw = enabled;
+        self
+    }
+
+    /// Fill the background of the output with `fill` instead of spaces.
+    pub fn background(mut self, fill: char) -> Self {
+        self.background = Some(fill);
+        self
+    }
+
+    /// Add a filter; filters run in the order they were added.
+    pub fn filter<F: Filter + 'static>(mut self, filter: F) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Swap `from` for `to` in the finished output, e.g. `#` for `█`.
+    pub fn substitute(mut self, from: char, to: char) -> Self {
+        self.substitutions.insert(from, to);
+        self
+    }
+
+    pub fn substitutions(mut self, map: HashMap<char, char>) -> Self {
+        self.substitutions.extend(map);
+        self
+    }
+
+    /// Widen the gap between `left` and `right` by `adjust` columns, or
+    /// narrow it when negative, on top of the font's own spacing.
+    pub fn kern(mut self, left: char, right: char, adjust: isize) -> Self {
+        self.kerning.insert((left, right), adjust);
+        self
+    }
+
+    pub fn kerning(mut self, pairs: HashMap<(char, char), isize>) -> Self {
+        self.kerning.extend(pairs);
+        self
+    }
+
+    /// Draw the glyph with code tag `code` wherever `sequence` appears in
+    /// the input, e.g. "fi" for a font's fi ligature. The longest matching
+    /// sequence wins, and sequences whose glyph the font lacks are ignored.
+    pub fn ligature(mut self, sequence: &str, code: i32) -> Self {
+        self.ligatures.push((sequence.to_string(), code));
+        self
+    }
+
+    pub fn missing(mut self, policy: MissingPolicy) -> Self {
+        self.missing = policy;
+        self
+    }
+
+    pub fn ansi(mut self, policy: AnsiPolicy) -> Self {
+        self.ansi = policy;
+        self
+    }
+
+    /// Interpret carriage returns, backspaces and tabs the way a terminal
+    /// would; see `input::ControlChars`.
+    pub fn control_chars(self, enabled: bool) -> Self {
+        if enabled {
+            self.input(ControlChars)
+        } else {
+            self
+        }
+    }
+
+    /// Reorder mixed-direction input into visual order before glyph lookup,
+    /// taking the base direction from the font's print direction. The
+    /// reordered text is laid out left to right, so a right-to-left base
+    /// direction only right-aligns it.
+    #[cfg(feature = "bidi")]
+    pub fn bidi(mut self, enabled: bool) -> Self {
+        if !enabled {
+            return self;
+        }
+        self.bidi = true;
+        let rtl = self.font.font_head.print_direction() == 1;
+        self.input(input::BidiReorder { rtl })
+    }
+
+    /// Override the font's print direction.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Align lines within the wrap width, or within the widest line when
+    /// not wrapping. Right-to-left text is right-aligned by default, other
+    /// text left-aligned.
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    /// Wrap the output so no line is wider than `width` columns.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// When wrapping, break long words at English hyphenation points.
+    #[cfg(feature = "hyphenation")]
+    pub fn hyphenate(mut self, enabled: bool) -> Self {
+        self.hyphenate = enabled;
+        self
+    }
+
+    /// How to break lines when wrapping; see `WrapPolicy`.
+    pub fn wrap_policy(mut self, policy: WrapPolicy) -> Self {
+        self.wrap_policy = policy;
+        self
+    }
+
+    /// Lay glyphs out with `rules` instead of the font's own, e.g. parsed
+    /// from a name like "kern" or "smush,equal,bigx".
+    pub fn rules(mut self, rules: Rules) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    /// Stack wrapped lines with `mode` instead of one under the other,
+    /// using the vertical rules of the font or of `rules`. Without this,
+    /// only vertical layout given through `rules` is applied.
+    pub fn vertical_layout(mut self, mode: LayoutMode) -> Self {
+        self.vertical = Some(mode);
+        self
+    }
+
+    /// Join rows with `ending` instead of `\n` in `convert`, e.g. `\r\n`
+    /// for Telnet or Windows consumers.
+    pub fn line_ending(mut self, ending: &str) -> Self {
+        self.line_ending = ending.to_string();
+        self
+    }
+
+    /// Give up on renders once `token` is cancelled, with
+    /// `RenderError::Cancelled` from `try_render` and an empty figure from
+    /// `render`.
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Refuse to produce output bigger than `limits`, with
+    /// `RenderError::TooLarge` from `try_render` and an empty figure from
+    /// `render`. Filters and padding count towards the size.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Render `message` to text, with rows joined by the line ending.
+    pub fn convert(&self, message: &str) -> String {
+        self.render(message).join(&self.line_ending)
+    }
+
+    /// Render `message` straight into `out`, e.g. an existing `String` or a
+    /// `fmt::Formatter`, with rows joined by the line ending.
+    pub fn write_to<W: fmt::Write>(&self, message: &str, out: &mut W) -> fmt::Result {
+        let figure = self.render(message);
+        for (i, row) in figure.rows().iter().enumerate() {
+            if i > 0 {
+                out.write_str(&self.line_ending)?;
+            }
+            for &c in row {
+                out.write_char(c)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `write_to`, for byte streams such as files and sockets.
+    pub fn write_io<W: io::Write>(&self, message: &str, out: &mut W) -> io::Result<()> {
+        out.write_all(self.convert(message).as_bytes())
+    }
+
+    /// Like `write_io`, for async writers. Nothing is done until the
+    /// future is polled; then the message is laid out a paragraph at a
+    /// time and the banner written a row at a time, handing control back
+    /// to the executor in between, so a big banner or a slow client
+    /// doesn't hold up other tasks. Figure-wide steps such as alignment
+    /// and filters need every line, so the laid-out banner is kept until
+    /// it's written; use `Limits` to bound it.
+    #[cfg(feature = "async")]
+    pub fn render_async<'r, W>(
+        &'r self,
+        message: &str,
+        out: &'r mut W,
+    ) -> impl Future<Output = io::Result<()>> + use<'a, 'r, W>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let message = message.to_string();
+        async move {
+            let policy = self.cleaned_policy();
+            let mut layout = self.start(&message);
+            let figure = loop {
+                match self.step(&mut layout, policy, None) {
+                    Ok(true) => yield_now().await,
+                    Ok(false) => break self.finish(layout),
+                    Err(e) => break Err(e),
+                }
+            };
+            for (i, row) in cleaned(figure).iter().enumerate() {
+                if i > 0 {
+                    yield_now().await;
+                    write_all_async(out, self.line_ending.as_bytes()).await?;
+                }
+                write_all_async(out, row.as_bytes()).await?;
+            }
+            future::poll_fn(|cx| Pin::new(&mut *out).poll_flush(cx)).await
+        }
+    }
+
+    /// Like `render`, but fails on input the renderer is set to reject.
+    pub fn try_render(&self, message: &str) -> Result<FIGure, RenderError> {
+        if self.ansi == AnsiPolicy::Reject {
+            if let Some(offset) = find_ansi(message) {
+                return Err(RenderError::AnsiEscape { offset });
+            }
+        }
+        self.render_with(message, self.wrap_policy, None)
+    }
+
+    /// Render `message`. Input that `try_render` would reject is cleaned up
+    /// instead, so `AnsiPolicy::Reject` strips escapes here and
+    /// `WrapPolicy::Error` breaks long words anywhere.
+    pub fn render(&self, message: &str) -> FIGure {
+        self.render_cleaned(message, None)
+    }
+
+    /// Render each of `messages` as `render` would, for services turning
+    /// out many banners with the same settings. Lines laid out for one
+    /// message, including the candidates tried while wrapping, are kept
+    /// and reused for the rest of the batch.
+    pub fn render_batch<'b, 'm, I>(
+        &'b self,
+        messages: I,
+    ) -> impl Iterator<Item = FIGure> + use<'a, 'b, 'm, I>
+    where
+        I: Iterator<Item = &'m str> + 'b,
+    {
+        let lines = LineCache::default();
+        messages.map(move |message| {
+            if lines.borrow().len() >= BATCH_CACHE_LINES {
+                lines.borrow_mut().clear();
+            }
+            self.render_cleaned(message, Some(&lines))
+        })
+    }
+
+    /// `render`, with its cache if any: long words are broken anywhere, and
+    /// a cancelled or oversized render gives an empty figure.
+    fn render_cleaned(&self, message: &str, cache: Option<&LineCache>) -> FIGure {
+        cleaned(self.render_with(message, self.cleaned_policy(), cache))
+    }
+
+    fn cleaned_policy(&self) -> WrapPolicy {
+        match self.wrap_policy {
+            WrapPolicy::Error => WrapPolicy::Anywhere,
+            policy => policy,
+        }
+    }
+
+    fn render_with(
+        &self,
+        message: &str,
+        policy: WrapPolicy,
+        cache: Option<&LineCache>,
+    ) -> Result<FIGure, RenderError> {
+        let mut layout = self.start(message);
+        while self.step(&mut layout, policy, cache)? {}
+        self.finish(layout)
+    }
+
+    /// Prepare `message` for layout: escapes, input filters and shaping
+    /// are applied, and the direction, width and rules settled.
+    fn start(&self, message: &str) -> Layout {
+        let message = if self.ansi == AnsiPolicy::Keep {
+            Cow::Borrowed(message)
+        } else {
+            strip_ansi(message)
+        };
+        let message = input::apply_all(&self.inputs, &message);
+        let message = shaping::shape_arabic(&message, |c| self.font.glyph(c).is_some());
+        let rtl = match self.direction {
+            None => self.font.font_head.print_direction() == 1,
+            Some(Direction::LeftToRight) => false,
+            Some(Direction::RightToLeft) => true,
+            Some(Direction::Auto) => shaping::starts_rtl(&message),
+        };
+        // figlet 2.2.5 keeps the last column free, so lines wrap and align
+        // one column short of the width.
+        let width = match cfg!(feature = "compat") {
+            true => self.width.map(|width| width.saturating_sub(1)),
+            false => self.width,
+        };
+        let mut rules = self
+            .rules
+            .clone()
+            .unwrap_or_else(|| self.font.rules.clone());
+        rules.vertical_layout = match (self.vertical, &self.rules) {
+            (Some(mode), _) => mode,
+            (None, Some(rules)) => rules.vertical_layout,
+            (None, None) => LayoutMode::FullWidth,
+        };
+        // Wrapping never joins paragraphs, so each can be laid out on its
+        // own. Unwrapped, the message is a single line.
+        let paragraphs: Vec<String> = match width {
+            Some(_) => message.split('\n').map(String::from).collect(),
+            None => vec![message.into_owned()],
+        };
+        Layout {
+            paragraphs: paragraphs.into_iter(),
+            rtl,
+            // Bidi reordering already put the text in visual order.
+            reverse: rtl && !self.bidi,
+            width,
+            rules,
+            lines: vec![],
+        }
+    }
+
+    /// Lay out the next paragraph of `layout`, returning whether there are
+    /// more.
+    fn step(
+        &self,
+        layout: &mut Layout,
+        policy: WrapPolicy,
+        cache: Option<&LineCache>,
+    ) -> Result<bool, RenderError> {
+        let paragraph = match layout.paragraphs.next() {
+            Some(paragraph) => paragraph,
+            None => return Ok(false),
+        };
+        let reverse = layout.reverse;
+        match layout.width {
+            Some(width) => {
+                let measure = |line: &str| self.render_line(line, reverse, cache).width();
+                let wrapped = wrap::wrap(&measure, &paragraph, width, policy, self.hyphenate)
+                    .map_err(|word| RenderError::WordTooWide { word, width })?;
+                // Stacked full width, the height is known before any line
+                // is laid out.
+                if layout.rules.vertical_layout == LayoutMode::FullWidth {
+                    let lines = layout.lines.len() + wrapped.len();
+                    self.check_size(0, lines * self.font.font_head.height())?;
+                }
+                for line in wrapped.iter() {
+                    layout.lines.push(self.render_line(line, reverse, cache));
+                }
+            }
+            None => layout
+                .lines
+                .push(self.render_line(&paragraph, reverse, cache)),
+        }
+        self.check_cancelled()?;
+        Ok(layout.paragraphs.len() > 0)
+    }
+
+    /// Align and stack the lines of `layout` and apply the figure filters.
+    fn finish(&self, layout: Layout) -> Result<FIGure, RenderError> {
+        let Layout {
+            rtl,
+            width,
+            rules,
+            lines,
+            ..
+        } = layout;
+        for line in lines.iter() {
+            self.check_size(line.width(), line.height())?;
+        }
+        let align = self
+            .align
+            .unwrap_or(if rtl { Align::Right } else { Align::Left });
+        let target = lines.iter().map(FIGure::width).max().unwrap_or(0);
+        let target = width.unwrap_or(0).max(target);
+        let mut rows = vec![];
+        for line in lines {
+            self.check_cancelled()?;
+            let indent = match align {
+                Align::Left => 0,
+                Align::Center => (target - line.width()) / 2,
+                Align::Right => target - line.width(),
+            };
+            let block: Vec<Vec<char>> = line
+                .rows()
+                .iter()
+                .map(|row| {
+                    let mut row = row.clone();
+                    row.splice(0..0, std::iter::repeat_n(' ', indent));
+                    row
+                })
+                .collect();
+            rows = figure::stack(&rows, &block, &rules, self.font.font_head.hardblank());
+            self.check_size(target, rows.len())?;
+        }
+        self.check_cancelled()?;
+        let mut figure = FIGure::new(rows);
+        if self.pad_to_rect {
+            figure.pad_to_rect();
+        }
+        for filter in self.filters.iter() {
+            figure = filter.apply(figure);
+        }
+        if let Some(fill) = self.background {
+            figure.pad_to_rect();
+            figure.replace(' ', fill);
+        }
+        if !self.substitutions.is_empty() {
+            figure.map_cells(|c| *self.substitutions.get(&c).unwrap_or(&c));
+        }
+        self.check_size(figure.width(), figure.height())?;
+        Ok(figure)
+    }
+
+    /// Render text given as `(text, style)` spans on one line, keeping
+    /// track of which span drew each output cell. Layout is as in `render`,
+    /// except that the spans aren't wrapped, aligned or passed through
+    /// input or figure filters, which could move cells between spans.
+    pub fn render_spans(&self, spans: &[(&str, Style)]) -> StyledFIGure {
+        let mut message = String::new();
+        let mut starts = vec![];
+        for (text, _) in spans.iter() {
+            starts.push(message.len());
+            message.push_str(text);
+        }
+        let rtl = match self.direction {
+            None => self.font.font_head.print_direction() == 1,
+            Some(Direction::LeftToRight) => false,
+            Some(Direction::RightToLeft) => true,
+            Some(Direction::Auto) => shaping::starts_rtl(&message),
+        };
+        let (mut figure, owners) = self
+            .font
+            .compose_traced(&message, &self.compose_options(rtl));
+        if !self.raw {
+            figure.replace(self.font.font_head.hardblank(), ' ');
+        }
+        // Hardblanks are ink to the font but blank on screen, so only
+        // cells that show something belong to a span.
+        let spans_at = owners
+            .iter()
+            .zip(figure.rows())
+            .map(|(row, cells)| {
+                row.iter()
+                    .zip(cells)
+                    .map(|(owner, &c)| match c {
+                        ' ' => None,
+                        _ => owner.map(|offset| starts.partition_point(|&s| s <= offset) - 1),
+                    })
+                    .collect()
+            })
+            .collect();
+        if self.pad_to_rect {
+            figure.pad_to_rect();
+        }
+        if let Some(fill) = self.background {
+            figure.pad_to_rect();
+            figure.replace(' ', fill);
+        }
+        if !self.substitutions.is_empty() {
+            figure.map_cells(|c| *self.substitutions.get(&c).unwrap_or(&c));
+        }
+        StyledFIGure {
+            figure,
+            spans: spans_at,
+            styles: spans.iter().map(|(_, style)| *style).collect(),
+        }
+    }
+
+    fn compose_options(&self, rtl: bool) -> Compose<'_> {
+        Compose {
+            rtl,
+            kerning: Some(&self.kerning),
+            ligatures: &self.ligatures,
+            missing: self.missing,
+            rules: self.rules.as_ref(),
+            cancel: self.cancel.as_ref(),
+            max_width: self.limits.line_width(self.font.font_head.height()),
+        }
+    }
+
+    fn check_size(&self, width: usize, height: usize) -> Result<(), RenderError> {
+        match self.limits.allow(width, height) {
+            true => Ok(()),
+            false => Err(RenderError::TooLarge { width, height }),
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<(), RenderError> {
+        match &self.cancel {
+            Some(token) if token.is_cancelled() => Err(RenderError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    fn render_line(&self, line: &str, rtl: bool, cache: Option<&LineCache>) -> FIGure {
+        let key = match cache {
+            Some(cache) => {
+                let key = (line.to_string(), rtl);
+                if let Some(figure) = cache.borrow().get(&key) {
+                    return figure.clone();
+                }
+                Some(key)
+            }
+            None => None,
+        };
+        let mut figure = self.font.compose(line, &self.compose_options(rtl));
+        if !self.raw {
+            figure.replace(self.font.font_head.hardblank(), ' ');
+        }
+        if let (Some(cache), Some(key)) = (cache, key) {
+            cache.borrow_mut().insert(key, figure.clone());
+        }
+        figure
+    }
+}
+
+/// What `render` gives for `result`: a cancelled or oversized render is an
+/// empty figure.
+fn cleaned(result: Result<FIGure, RenderError>) -> FIGure {
+    match result {
+        Ok(figure) => figure,
+        Err(RenderError::Cancelled | RenderError::TooLarge { .. }) => FIGure::new(vec![]),
+        Err(RenderError::AnsiEscape { .. } | RenderError::WordTooWide { .. }) => {
+            unreachable!("only WrapPolicy::Error fails")
+        }
+    }
+}
+
+#[test]
+fn case_transform() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let upper = Renderer::new(&f).case(Case::Upper).render("FIGlet");
+    assert_eq!(upper.to_string(), f.convert("FIGLET"));
+    let lower = Renderer::new(&f).case(Case::Lower).render("FIGlet");
+    assert_eq!(lower.to_string(), f.convert("figlet"));
+}
+
+#[test]
+fn background_fill() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let filled = Renderer::new(&f).background('.').render("I I").to_string();
+    assert!(!filled.contains(' '));
+    assert_eq!(filled.replace('.', " "), f.render("I I").to_string());
+}
+
+#[test]
+fn filters() {
+    use crate::filter::Shadow;
+    let f = Font::load_font("Standard.flf").unwrap();
+    let plain = f.render("I");
+    let shadowed = Renderer::new(&f).filter(Shadow::new(1, 1, '.')).render("I");
+    assert_eq!(shadowed.width(), plain.width() + 1);
+    assert_eq!(shadowed.height(), plain.height() + 1);
+}
+
+#[test]
+fn substitutions() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let plain = f.render("_|").to_string();
+    let restyled = Renderer::new(&f)
+        .substitute('_', '▁')
+        .substitutions(HashMap::from([('|', '│')]))
+        .render("_|")
+        .to_string();
+    assert_eq!(restyled, plain.replace('_', "▁").replace('|', "│"));
+}
+
+#[test]
+fn ansi_policy() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let colored = "\x1b[32mok\x1b[0m";
+    let plain = f.render("ok").to_string();
+    assert_eq!(Renderer::new(&f).render(colored).to_string(), plain);
+    let strict = Renderer::new(&f).ansi(AnsiPolicy::Reject);
+    assert!(matches!(
+        strict.try_render(colored),
+        Err(RenderError::AnsiEscape { offset: 0 })
+    ));
+    assert_eq!(strict.try_render("ok").unwrap().to_string(), plain);
+}
+
+#[test]
+fn input_order() {
+    #[derive(Debug)]
+    struct Ellipsis;
+    impl InputFilter for Ellipsis {
+        fn apply<'a>(&self, message: &'a str) -> Cow<'a, str> {
+            Cow::Owned(message.replace("...", "!"))
+        }
+    }
+
+    let f = Font::load_font("Standard.flf").unwrap();
+    let rendered = Renderer::new(&f)
+        .ascii_punctuation(true)
+        .input(Ellipsis)
+        .render("a\u{2026}");
+    assert_eq!(rendered.to_string(), f.convert("a!"));
+    let rendered = Renderer::new(&f)
+        .input(Ellipsis)
+        .ascii_punctuation(true)
+        .render("a\u{2026}");
+    assert_eq!(rendered.to_string(), f.convert("a..."));
+}
+
+#[test]
+fn wrapping() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    // figlet 2.2.5 leaves the last column free.
+    let width = f.render("Hi").width() + usize::from(cfg!(feature = "compat"));
+    let wrapped = Renderer::new(&f).width(width).render("Hi Hi");
+    assert_eq!(
+        wrapped.to_string(),
+        format!("{}\n{}", f.render("Hi"), f.render("Hi"))
+    );
+}
+
+#[test]
+fn wrap_policies() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let width = f.render("Hi").width() + usize::from(cfg!(feature = "compat"));
+    let renderer = Renderer::new(&f).width(width);
+    assert!(renderer.render("Hiya").height() > f.render("Hi").height());
+    let words = Renderer::new(&f)
+        .width(width)
+        .wrap_policy(WrapPolicy::Words);
+    assert_eq!(
+        words.render("Hiya").to_string(),
+        f.render("Hiya").to_string()
+    );
+    let strict = Renderer::new(&f)
+        .width(width)
+        .wrap_policy(WrapPolicy::Error);
+    assert!(matches!(
+        strict.try_render("Hi Hiya"),
+        Err(RenderError::WordTooWide { ref word, .. }) if word == "Hiya"
+    ));
+    assert_eq!(
+        strict.render("Hiya").to_string(),
+        renderer.render("Hiya").to_string()
+    );
+}
+
+#[test]
+fn right_to_left() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let rtl = Renderer::new(&f).direction(Direction::RightToLeft);
+    assert_eq!(rtl.render("ab").to_string(), f.convert("ba"));
+    let auto = Renderer::new(&f).direction(Direction::Auto);
+    assert_eq!(auto.render("ab").to_string(), f.convert("ab"));
+
+    let ivrit = Font::load_font("Ivrit.flf").unwrap();
+    let shalom = "\u{5e9}\u{5dc}\u{5d5}\u{5dd}";
+    let reversed: String = shalom.chars().rev().collect();
+    let rendered = Renderer::new(&ivrit).render(shalom);
+    assert_eq!(
+        rendered.to_string(),
+        Renderer::new(&ivrit)
+            .direction(Direction::LeftToRight)
+            .render(&reversed)
+            .to_string()
+    );
+
+    // Wrapped right-to-left lines are right-aligned within the width.
+    let width = ivrit.render(shalom).width() + 4;
+    let wrapped = Renderer::new(&ivrit)
+        .width(width)
+        .render(&format!("{} \u{5d0}", shalom));
+    let used = width - usize::from(cfg!(feature = "compat"));
+    assert!(wrapped.rows().iter().all(|row| row.len() == used));
+    let last = wrapped.rows().last().unwrap();
+    assert_eq!(last[0], ' ');
+}
+
+#[cfg(feature = "bidi")]
+#[test]
+fn bidi_right_to_left_font() {
+    let ivrit = Font::load_font("Ivrit.flf").unwrap();
+    let ltr = Renderer::new(&ivrit).direction(Direction::LeftToRight);
+    // The Latin run keeps its order and the Hebrew run comes first.
+    let expected = ltr.render("\u{5d1}\u{5d0} ab").to_string();
+    let bidi = Renderer::new(&ivrit).bidi(true);
+    assert_eq!(bidi.render("ab \u{5d0}\u{5d1}").to_string(), expected);
+
+    // Lines narrower than the width are still right-aligned.
+    let width = ivrit.render("ab \u{5d0}").width() + 4;
+    let wrapped = Renderer::new(&ivrit)
+        .bidi(true)
+        .width(width)
+        .render("ab \u{5d0}");
+    assert!(wrapped.rows().iter().all(|row| row[0] == ' '));
+}
+
+#[test]
+fn alignment() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let width = f.render("Hi").width() + 4;
+    let centered = Renderer::new(&f)
+        .width(width)
+        .align(Align::Center)
+        .render("Hi");
+    assert_eq!(centered.rows()[1][..2], [' ', ' ']);
+    let left = Renderer::new(&f).width(width).render("Hi");
+    assert_eq!(left.to_string(), f.convert("Hi"));
+}
+
+#[test]
+fn kerning_pairs() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let plain = f.render("AV");
+    let wide = Renderer::new(&f).kern('A', 'V', 2).render("AV");
+    assert_eq!(wide.width(), plain.width() + 2);
+    let tight = Renderer::new(&f)
+        .kerning(HashMap::from([(('A', 'V'), -1)]))
+        .render("AVA");
+    assert_eq!(tight.width(), f.render("AVA").width() - 1);
+    assert_eq!(
+        Renderer::new(&f).kern('V', 'A', 3).render("AV").to_string(),
+        plain.to_string()
+    );
+}
+
+#[test]
+fn ligatures() {
+    let mut f = Font::load_font("Standard.flf").unwrap();
+    let x = f.glyph('X').unwrap().rows().to_vec();
+    f.insert_glyph(crate::glyph::Glyph::new(-2, x));
+    let renderer = Renderer::new(&f)
+        .ligature("fi", -2)
+        .ligature("f", 0x7fff_0000);
+    assert_eq!(renderer.render("fif").to_string(), f.convert("Xf"));
+    let longest = Renderer::new(&f)
+        .ligature("f", -2)
+        .ligature("ffi", 'Y' as i32);
+    assert_eq!(longest.render("fffi").to_string(), f.convert("XY"));
+}
+
+#[test]
+fn missing_glyphs() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let rocket = "a\u{1f680}b";
+    assert_eq!(f.convert(rocket), f.convert("ab"));
+    let placeholder = Renderer::new(&f).missing(MissingPolicy::Placeholder('?'));
+    assert_eq!(placeholder.render(rocket).to_string(), f.convert("a?b"));
+    let skip = Renderer::new(&f).missing(MissingPolicy::Skip);
+    assert_eq!(skip.render(rocket).to_string(), f.convert("ab"));
+    #[cfg(feature = "emoji")]
+    {
+        let shortcode = Renderer::new(&f).missing(MissingPolicy::Shortcode);
+        assert_eq!(
+            shortcode.render("\u{1f680}\u{fe0f}").to_string(),
+            f.convert(":rocket:")
+        );
+    }
+}
+
+#[test]
+fn control_chars() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let legacy = Renderer::new(&f).control_chars(true);
+    assert_eq!(legacy.render("ab\rc").to_string(), f.convert("cb"));
+}
+
+#[test]
+fn rules_override() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let full = Renderer::new(&f)
+        .rules("full".parse().unwrap())
+        .render("Hi");
+    assert_eq!(
+        full.to_string(),
+        f.convert("H")
+            .lines()
+            .zip(f.convert("i").lines())
+            .map(|(h, i)| format!("{}{}", h, i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    let kern = Renderer::new(&f)
+        .rules("kern".parse().unwrap())
+        .render("Hi");
+    assert!(kern.width() < full.width());
+    assert!(kern.width() > f.render("Hi").width());
+    assert_eq!(Renderer::new(&f).render("Hi").to_string(), f.convert("Hi"));
+}
+
+#[test]
+fn line_endings() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let plain = Renderer::new(&f).convert("Hi");
+    assert_eq!(plain, f.convert("Hi"));
+    let crlf = Renderer::new(&f).line_ending("\r\n").convert("Hi");
+    assert_eq!(crlf, plain.replace('\n', "\r\n"));
+    assert_eq!(crlf.matches("\r\n").count(), f.font_head.height() - 1);
+}
+
+#[cfg(feature = "async")]
+async fn write_all_async<W>(out: &mut W, mut buf: &[u8]) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    while !buf.is_empty() {
+        let n = future::poll_fn(|cx| Pin::new(&mut *out).poll_write(cx, buf)).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        buf = &buf[n..];
+    }
+    Ok(())
+}
+
+/// Return to the executor once, asking to be polled again right away.
+#[cfg(feature = "async")]
+async fn yield_now() {
+    let mut yielded = false;
+    future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
+#[test]
+fn write_to() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let renderer = Renderer::new(&f).line_ending("\r\n");
+    let mut out = String::from("> ");
+    renderer.write_to("Hi", &mut out).unwrap();
+    assert_eq!(out, format!("> {}", renderer.convert("Hi")));
+    let mut bytes = vec![];
+    renderer.write_io("Hi", &mut bytes).unwrap();
+    assert_eq!(bytes, renderer.convert("Hi").into_bytes());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn render_async() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Wake};
+
+    struct Wakes(AtomicUsize);
+    impl Wake for Wakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    // Polls the future to the end, returning what it wrote and how many
+    // times it yielded.
+    fn run(renderer: &Renderer, message: &str) -> (Vec<u8>, usize) {
+        let wakes = Arc::new(Wakes(AtomicUsize::new(0)));
+        let waker = wakes.clone().into();
+        let mut cx = Context::from_waker(&waker);
+        let mut out = vec![];
+        let mut writing = Box::pin(renderer.render_async(message, &mut out));
+        let mut polls = 1;
+        while writing.as_mut().poll(&mut cx).is_pending() {
+            polls += 1;
+        }
+        drop(writing);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), polls - 1);
+        (out, polls - 1)
+    }
+
+    let f = Font::load_font("Standard.flf").unwrap();
+    let renderer = Renderer::new(&f).line_ending("\r\n");
+    let (out, yields) = run(&renderer, "Hi");
+    assert_eq!(out, renderer.convert("Hi").into_bytes());
+    // One yield between each pair of rows.
+    assert_eq!(yields, f.font_head.height() - 1);
+
+    // And one between each pair of paragraphs.
+    let wrapped = Renderer::new(&f).width(40).align(Align::Center);
+    let message = "the quick brown fox\njumps over\nthe lazy dog";
+    let (out, yields) = run(&wrapped, message);
+    let figure = wrapped.render(message);
+    assert_eq!(out, wrapped.convert(message).into_bytes());
+    assert_eq!(yields, 2 + figure.height() - 1);
+
+    // Nothing is laid out before the first poll.
+    let token = CancelToken::new();
+    let cancelled = Renderer::new(&f).cancel_token(token.clone());
+    let mut out = vec![];
+    let writing = cancelled.render_async("Hi", &mut out);
+    token.cancel();
+    let mut writing = Box::pin(writing);
+    let waker = Arc::new(Wakes(AtomicUsize::new(0))).into();
+    assert!(writing
+        .as_mut()
+        .poll(&mut Context::from_waker(&waker))
+        .is_ready());
+    drop(writing);
+    assert!(out.is_empty());
+}
+
+#[test]
+fn cancellation() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let token = CancelToken::new();
+    let renderer = Renderer::new(&f).width(40).cancel_token(token.clone());
+    let message = "the quick brown fox jumps over the lazy dog ".repeat(8);
+    assert_eq!(
+        renderer.try_render(&message).unwrap(),
+        Renderer::new(&f).width(40).render(&message)
+    );
+    token.cancel();
+    assert!(token.is_cancelled());
+    assert!(matches!(
+        renderer.try_render(&message),
+        Err(RenderError::Cancelled)
+    ));
+    assert_eq!(renderer.render(&message).height(), 0);
+    // Laying out a single line stops early too.
+    let mut figure = vec![];
+    f.compose_into(
+        &message,
+        &renderer.compose_options(false),
+        &mut figure,
+        &mut Default::default(),
+        None,
+        None,
+    );
+    assert!(figure.iter().all(|row| row.is_empty()));
+}
+
+#[test]
+fn size_limits() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let message = "hello ".repeat(2000);
+    let wide = Renderer::new(&f).limits(Limits::new().width(80));
+    match wide.try_render(&message) {
+        // Layout stops soon after the limit is passed.
+        Err(RenderError::TooLarge { width, height }) => {
+            assert!(width > 80 && width < 100);
+            assert_eq!(height, f.font_head.height());
+        }
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(wide.render(&message).height(), 0);
+    let narrow = Renderer::new(&f).limits(Limits::new().width(10));
+    let batch: Vec<FIGure> = narrow
+        .render_batch(["hello world", ""].iter().copied())
+        .collect();
+    assert_eq!(batch[0].height(), 0);
+    assert_eq!(batch[1], narrow.render(""));
+    assert_eq!(wide.try_render("hello").unwrap(), f.render("hello"));
+
+    let tall = Renderer::new(&f).width(80).limits(Limits::new().height(20));
+    assert!(tall.try_render("hello").is_ok());
+    assert!(matches!(
+        tall.try_render(&"hello ".repeat(40)),
+        Err(RenderError::TooLarge { width: 0, height }) if height > 20
+    ));
+    let cells = Renderer::new(&f).limits(Limits::new().cells(100));
+    assert!(cells.try_render("hello").is_err());
+    // Filters count too.
+    let scaled = Renderer::new(&f)
+        .filter(crate::filter::Scale::new(1, 4))
+        .limits(Limits::new().height(20));
+    assert!(scaled.try_render("hi").is_err());
+    assert!(Limits::new().allow(usize::MAX, usize::MAX));
+}
+
+#[test]
+fn styled_spans() {
+    use crate::color::Color;
+    let f = Font::load_font("Standard.flf").unwrap();
+    let red = Style::fg(Color::rgb(255, 0, 0));
+    let blue = Style::fg(Color::rgb(0, 0, 255));
+    let styled = Renderer::new(&f).render_spans(&[("Hi ", red), ("", blue), ("you", blue)]);
+    assert_eq!(styled.figure, f.render("Hi you"));
+    let hi = f.render("Hi").width();
+    for (y, row) in styled.figure.rows().iter().enumerate() {
+        for (x, &c) in row.iter().enumerate() {
+            match styled.spans[y].get(x).copied().flatten() {
+                None => assert_eq!(c, ' '),
+                Some(span) => {
+                    assert_ne!(c, ' ');
+                    assert_eq!(span, if x < hi { 0 } else { 2 }, "{} {}", y, x);
+                }
+            }
+        }
+    }
+    assert!(styled.paint().contains(&blue.ansi()));
+
+    let split = Renderer::new(&f).render_spans(&[("H", red), ("i", blue)]);
+    let cells: Vec<Option<usize>> = split.spans.concat();
+    assert!(cells.contains(&Some(0)) && cells.contains(&Some(1)));
+}
+
+#[test]
+fn vertical_layout() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let full = Renderer::new(&f).width(20).render("ab cd");
+    let fitted = Renderer::new(&f)
+        .width(20)
+        .vertical_layout(LayoutMode::Fitting)
+        .render("ab cd");
+    assert_eq!(full.height(), 2 * f.font_head.height());
+    assert!(fitted.height() < full.height());
+    assert_eq!(fitted.rows()[0], full.rows()[0]);
+    let mut rows = fitted.rows().to_vec();
+    rows.truncate(fitted.height() - f.font_head.height());
+    assert!(full.rows().starts_with(&rows));
+}
+
+#[test]
+fn batch_rendering() {
+    let f = Font::load_font("Standard.flf").unwrap();
+    let messages = ["Hello", "Hello world", "", "Hello", "wrapped text here"];
+    let renderer = Renderer::new(&f).width(40).align(Align::Center);
+    let batch: Vec<FIGure> = renderer.render_batch(messages.iter().copied()).collect();
+    let single: Vec<FIGure> = messages.iter().map(|m| renderer.render(m)).collect();
+    assert_eq!(batch, single);
+}
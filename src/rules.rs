@@ -1,6 +1,9 @@
+use crate::error::LayoutError;
 use crate::layout::*;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Rules {
     pub horizontal_layout: LayoutMode,
     pub vertical_layout: LayoutMode,
@@ -9,11 +12,71 @@ pub struct Rules {
 }
 
 impl Rules {
+    /// Decode a font header's layout fields into smushing rules. The full
+    /// layout wins when present; otherwise the old layout is used.
+    pub fn from_layout(full_layout: Option<LayoutFlags>, old_layout: i32) -> Rules {
+        Rules::from_flags(full_layout.unwrap_or_else(|| LayoutFlags::from_old_layout(old_layout)))
+    }
+
+    /// The rules a full layout field's bits stand for. Any smushing rule
+    /// makes the layout controlled smushing; otherwise the fitting and
+    /// smush bits decide it, and with neither it's full width.
+    pub fn from_flags(flags: LayoutFlags) -> Rules {
+        let set = |kind: LayoutType| -> Vec<SmushingRule> {
+            let mut rules: Vec<SmushingRule> = SmushingRule::iter()
+                .filter(|rule| flags.contains(rule.flag()) && rule.get_type() == kind)
+                .collect();
+            rules.reverse();
+            rules
+        };
+        // Rules are kept highest bit first, and the lowest one set decides
+        // the layout mode.
+        let resolve = |mut rules: Vec<SmushingRule>, smushing: SmushingRule| match rules
+            .last()
+            .map(|rule| rule.get_mode())
+        {
+            None => (LayoutMode::FullWidth, rules),
+            Some(LayoutMode::ControlledSmush) => {
+                rules.retain(|rule| *rule != smushing);
+                (LayoutMode::ControlledSmush, rules)
+            }
+            Some(mode) => (mode, rules),
+        };
+        let (horizontal_layout, horizontal_rules) = resolve(
+            set(LayoutType::Horizontal),
+            SmushingRule::HorizontalSmushing,
+        );
+        let (vertical_layout, vertical_rules) =
+            resolve(set(LayoutType::Vertical), SmushingRule::VerticalSmushing);
+        Rules {
+            horizontal_layout,
+            vertical_layout,
+            horizontal_rules,
+            vertical_rules,
+        }
+    }
+
     pub fn smushes_horizontal(&self, char1: char, char2: char, hardblank: char) -> bool {
         self.horizontal_rules
             .iter()
             .any(|r| r.smush(char1, char2, hardblank).is_some())
     }
+    /// The rule that would smush two non-space characters, if any.
+    pub fn horizontal_rule(
+        &self,
+        char1: char,
+        char2: char,
+        hardblank: char,
+    ) -> Option<SmushingRule> {
+        if self.horizontal_layout == LayoutMode::UniversalSmush {
+            let rule = SmushingRule::HorizontalSmushing;
+            return rule.smush(char1, char2, hardblank).map(|_| rule);
+        }
+        self.horizontal_rules
+            .iter()
+            .cloned()
+            .find(|r| r.smush(char1, char2, hardblank).is_some())
+    }
     pub fn smush_horizontal(&self, char1: char, char2: char, hardblank: char) -> Option<char> {
         if char1 == ' ' {
             return Some(char2);
@@ -33,6 +96,38 @@ impl Rules {
         }
         None
     }
+
+    /// Like `smushes_horizontal`, for `char1` directly above `char2`.
+    pub fn smushes_vertical(&self, char1: char, char2: char, hardblank: char) -> bool {
+        self.vertical_rules
+            .iter()
+            .any(|r| r.smush(char1, char2, hardblank).is_some())
+    }
+
+    /// Like `horizontal_rule`, for `char1` directly above `char2`.
+    pub fn vertical_rule(&self, char1: char, char2: char, hardblank: char) -> Option<SmushingRule> {
+        if self.vertical_layout == LayoutMode::UniversalSmush {
+            let rule = SmushingRule::VerticalSmushing;
+            return rule.smush(char1, char2, hardblank).map(|_| rule);
+        }
+        self.vertical_rules
+            .iter()
+            .cloned()
+            .find(|r| r.smush(char1, char2, hardblank).is_some())
+    }
+
+    /// What `char1` becomes with `char2` smushed into it from below, or
+    /// `None` if the vertical rules don't allow it.
+    pub fn smush_vertical(&self, char1: char, char2: char, hardblank: char) -> Option<char> {
+        if char1 == ' ' {
+            return Some(char2);
+        }
+        if char2 == ' ' {
+            return Some(char1);
+        }
+        let rule = self.vertical_rule(char1, char2, hardblank)?;
+        rule.smush(char1, char2, hardblank)
+    }
 }
 
 impl Default for Rules {
@@ -45,3 +140,399 @@ impl Default for Rules {
         }
     }
 }
+
+impl From<LayoutFlags> for Rules {
+    fn from(flags: LayoutFlags) -> Self {
+        Rules::from_flags(flags)
+    }
+}
+
+impl FromStr for Rules {
+    type Err = LayoutError;
+
+    /// Parse a list of layout mode and rule names separated by commas or
+    /// spaces, e.g. "smush, equal, hierarchy". Rules alone imply controlled
+    /// smushing, and "smush" without any horizontal rules smushes
+    /// universally, as in a font header.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut rules = Rules::default();
+        let mut mode = None;
+        let names = spec
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|name| !name.is_empty());
+        for name in names {
+            if let Ok(m) = name.parse::<LayoutMode>() {
+                mode = Some(m);
+                continue;
+            }
+            let rule: SmushingRule = name.parse()?;
+            let list = match rule.get_type() {
+                LayoutType::Horizontal => &mut rules.horizontal_rules,
+                LayoutType::Vertical => &mut rules.vertical_rules,
+            };
+            if !list.contains(&rule) {
+                list.push(rule);
+            }
+        }
+        let controlled = !rules.horizontal_rules.is_empty();
+        let mode = match mode {
+            Some(mode) => mode,
+            None if controlled => LayoutMode::ControlledSmush,
+            None if rules.vertical_rules.is_empty() => return Err(LayoutError::Empty),
+            None => LayoutMode::FullWidth,
+        };
+        rules.horizontal_layout =
+            apply_mode(&mut rules.horizontal_rules, mode, LayoutType::Horizontal);
+        if !rules.vertical_rules.is_empty() {
+            rules.vertical_layout = LayoutMode::ControlledSmush;
+        }
+        Ok(rules)
+    }
+}
+
+/// Make `rules` fit the layout `mode`: fitting, universal smushing and full
+/// width replace them with their own mode rule or none, and controlled
+/// smushing without any rules smushes universally, as in a font header.
+/// Returns the mode the rules now follow.
+fn apply_mode(rules: &mut Vec<SmushingRule>, mode: LayoutMode, kind: LayoutType) -> LayoutMode {
+    let (fitting, smushing) = match kind {
+        LayoutType::Horizontal => (
+            SmushingRule::HorizontalFitting,
+            SmushingRule::HorizontalSmushing,
+        ),
+        LayoutType::Vertical => (
+            SmushingRule::VerticalFitting,
+            SmushingRule::VerticalSmushing,
+        ),
+    };
+    let mode = match mode {
+        LayoutMode::ControlledSmush if rules.is_empty() => LayoutMode::UniversalSmush,
+        mode => mode,
+    };
+    match mode {
+        LayoutMode::Fitting => *rules = vec![fitting],
+        LayoutMode::UniversalSmush => *rules = vec![smushing],
+        LayoutMode::FullWidth => rules.clear(),
+        LayoutMode::ControlledSmush => {}
+    }
+    mode
+}
+
+/// A typed way to build `Rules`, e.g. for `Renderer::rules`:
+/// `RuleSet::new().enable(SmushingRule::HorizontalEqualChar).build()`.
+/// Enabling a smushing rule implies controlled smushing unless a mode is
+/// set explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RuleSet {
+    flags: LayoutFlags,
+    horizontal: Option<LayoutMode>,
+    vertical: Option<LayoutMode>,
+}
+
+impl RuleSet {
+    /// No rules and full width both ways.
+    pub fn new() -> Self {
+        RuleSet::default()
+    }
+
+    /// Start from existing rules, such as a font's, to adjust them.
+    pub fn from_rules(rules: &Rules) -> Self {
+        RuleSet {
+            flags: rules
+                .horizontal_rules
+                .iter()
+                .chain(rules.vertical_rules.iter())
+                .fold(LayoutFlags::empty(), |flags, rule| flags | rule.flag()),
+            horizontal: Some(rules.horizontal_layout),
+            vertical: Some(rules.vertical_layout),
+        }
+    }
+
+    pub fn enable(mut self, rule: SmushingRule) -> Self {
+        self.flags |= rule.flag();
+        self
+    }
+
+    pub fn disable(mut self, rule: SmushingRule) -> Self {
+        self.flags -= rule.flag();
+        self
+    }
+
+    pub fn horizontal_mode(mut self, mode: LayoutMode) -> Self {
+        self.horizontal = Some(mode);
+        self
+    }
+
+    pub fn vertical_mode(mut self, mode: LayoutMode) -> Self {
+        self.vertical = Some(mode);
+        self
+    }
+
+    pub fn is_enabled(&self, rule: SmushingRule) -> bool {
+        self.flags.contains(rule.flag())
+    }
+
+    /// The rules, kept in bit order as `Rules::from_flags` keeps them.
+    pub fn build(&self) -> Rules {
+        let kind = |kind: LayoutType| -> Vec<SmushingRule> {
+            let mut rules: Vec<SmushingRule> = SmushingRule::iter()
+                .filter(|rule| self.is_enabled(*rule) && rule.get_type() == kind)
+                .collect();
+            rules.reverse();
+            rules
+        };
+        let mode = |rules: &mut Vec<SmushingRule>, mode: Option<LayoutMode>, kind| {
+            let mode = mode.unwrap_or(match rules.is_empty() {
+                true => LayoutMode::FullWidth,
+                false => LayoutMode::ControlledSmush,
+            });
+            apply_mode(rules, mode, kind)
+        };
+        let mut horizontal_rules = kind(LayoutType::Horizontal);
+        let mut vertical_rules = kind(LayoutType::Vertical);
+        Rules {
+            horizontal_layout: mode(
+                &mut horizontal_rules,
+                self.horizontal,
+                LayoutType::Horizontal,
+            ),
+            vertical_layout: mode(&mut vertical_rules, self.vertical, LayoutType::Vertical),
+            horizontal_rules,
+            vertical_rules,
+        }
+    }
+}
+
+impl From<RuleSet> for Rules {
+    fn from(set: RuleSet) -> Self {
+        set.build()
+    }
+}
+
+#[test]
+fn from_layout_full_width() {
+    let l = Rules::from_layout(Some(LayoutFlags::empty()), -1);
+    assert_eq!(l.horizontal_layout, LayoutMode::FullWidth);
+    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
+    assert_eq!(l.horizontal_rules.len(), 0);
+    assert_eq!(l.vertical_rules.len(), 0);
+
+    let l = Rules::from_layout(None, -1);
+    assert_eq!(l.horizontal_layout, LayoutMode::FullWidth);
+    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
+    assert_eq!(l.horizontal_rules.len(), 0);
+    assert_eq!(l.vertical_rules.len(), 0);
+}
+
+#[test]
+fn from_layout_kerning() {
+    let l = Rules::from_layout(Some(LayoutFlags::HORIZONTAL_FITTING), 0);
+    assert_eq!(l.horizontal_layout, LayoutMode::Fitting);
+    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
+    assert_eq!(l.horizontal_rules.len(), 1);
+    assert_eq!(
+        l.horizontal_rules.first().unwrap(),
+        &SmushingRule::HorizontalFitting
+    );
+    assert_eq!(l.vertical_rules.len(), 0);
+}
+
+#[test]
+fn from_layout_smushing() {
+    let l = Rules::from_layout(Some(LayoutFlags::HORIZONTAL_SMUSH), 0);
+    assert_eq!(l.horizontal_layout, LayoutMode::UniversalSmush);
+    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
+    assert_eq!(l.horizontal_rules.len(), 1);
+    assert_eq!(
+        l.horizontal_rules.first().unwrap(),
+        &SmushingRule::HorizontalSmushing
+    );
+    assert_eq!(l.vertical_rules.len(), 0);
+}
+
+#[test]
+fn from_layout_controlled_smushing_slant() {
+    // slant.flf
+    let l = Rules::from_layout(Some(LayoutFlags::from_bits_retain(18319)), 15);
+    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
+    assert_eq!(l.vertical_layout, LayoutMode::ControlledSmush);
+    assert_eq!(l.horizontal_rules.len(), 4);
+    assert_eq!(l.vertical_rules.len(), 3);
+
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalOppositePair));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalHierarchy));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalUnderscore));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalEqualChar));
+
+    assert!(l.vertical_rules.contains(&SmushingRule::VerticalHierarchy));
+    assert!(l.vertical_rules.contains(&SmushingRule::VerticalUnderscore));
+    assert!(l.vertical_rules.contains(&SmushingRule::VerticalEqualChar));
+}
+
+#[test]
+fn from_layout_controlled_smushing_standard() {
+    // starndard.flf
+    let l = Rules::from_layout(Some(LayoutFlags::from_bits_retain(24463)), 15);
+    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
+    assert_eq!(l.vertical_layout, LayoutMode::ControlledSmush);
+    assert_eq!(l.horizontal_rules.len(), 4);
+    assert_eq!(l.vertical_rules.len(), 5);
+
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalOppositePair));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalHierarchy));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalUnderscore));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalEqualChar));
+
+    assert!(l
+        .vertical_rules
+        .contains(&SmushingRule::VerticalVerticalLine));
+    assert!(l.vertical_rules.contains(&SmushingRule::VerticalHierarchy));
+    assert!(l.vertical_rules.contains(&SmushingRule::VerticalUnderscore));
+    assert!(l.vertical_rules.contains(&SmushingRule::VerticalEqualChar));
+
+    let l = Rules::from_layout(None, 15);
+    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
+    assert_eq!(l.horizontal_rules.len(), 4);
+    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
+
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalOppositePair));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalHierarchy));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalUnderscore));
+    assert!(l
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalEqualChar));
+}
+
+#[test]
+fn from_layout_fallbacks() {
+    // Georgi16.flf: full layout 0 means full width, whatever the old says.
+    let l = Rules::from_layout(Some(LayoutFlags::empty()), 15);
+    assert_eq!(l.horizontal_layout, LayoutMode::FullWidth);
+    let l = Rules::from_layout(None, 0);
+    assert_eq!(l.horizontal_layout, LayoutMode::Fitting);
+    assert_eq!(l.horizontal_rules, vec![SmushingRule::HorizontalFitting]);
+    assert!(l.vertical_rules.is_empty());
+}
+
+#[test]
+fn parse_rules() {
+    let l: Rules = "smush, equal hierarchy,vline".parse().unwrap();
+    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
+    assert_eq!(
+        l.horizontal_rules,
+        vec![
+            SmushingRule::HorizontalEqualChar,
+            SmushingRule::HorizontalHierarchy
+        ]
+    );
+    assert_eq!(l.vertical_layout, LayoutMode::ControlledSmush);
+    assert_eq!(l.vertical_rules, vec![SmushingRule::VerticalVerticalLine]);
+
+    let l: Rules = "kern".parse().unwrap();
+    assert_eq!(l.horizontal_layout, LayoutMode::Fitting);
+    assert_eq!(l.horizontal_rules, vec![SmushingRule::HorizontalFitting]);
+    let l: Rules = "smush".parse().unwrap();
+    assert_eq!(l.horizontal_layout, LayoutMode::UniversalSmush);
+    let l: Rules = "pair".parse().unwrap();
+    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
+
+    assert_eq!(" ,".parse::<Rules>().unwrap_err(), LayoutError::Empty);
+    assert_eq!(
+        "smush,wobble".parse::<Rules>().unwrap_err(),
+        LayoutError::UnknownRule("wobble".to_string())
+    );
+}
+
+#[test]
+fn from_flags() {
+    let flags = LayoutFlags::HORIZONTAL_EQUAL | LayoutFlags::HORIZONTAL_HIERARCHY;
+    let l = Rules::from(flags | LayoutFlags::HORIZONTAL_SMUSH);
+    assert_eq!(l.horizontal_layout, LayoutMode::ControlledSmush);
+    assert_eq!(
+        l.horizontal_rules,
+        vec![
+            SmushingRule::HorizontalHierarchy,
+            SmushingRule::HorizontalEqualChar
+        ]
+    );
+    assert_eq!(l.vertical_layout, LayoutMode::FullWidth);
+    let l = Rules::from(LayoutFlags::VERTICAL_FITTING);
+    assert_eq!(l.horizontal_layout, LayoutMode::FullWidth);
+    assert_eq!(l.vertical_layout, LayoutMode::Fitting);
+}
+
+#[test]
+fn smush_vertical() {
+    let l = Rules::from_flags(
+        LayoutFlags::VERTICAL_HORIZONTAL_LINE | LayoutFlags::VERTICAL_VERTICAL_LINE,
+    );
+    assert_eq!(l.vertical_layout, LayoutMode::ControlledSmush);
+    assert_eq!(l.smush_vertical('-', '_', '$'), Some('='));
+    assert_eq!(l.smush_vertical('|', '|', '$'), Some('|'));
+    assert_eq!(l.smush_vertical(' ', '/', '$'), Some('/'));
+    assert_eq!(l.smush_vertical('/', '\\', '$'), None);
+    assert!(l.smushes_vertical('_', '-', '$'));
+    assert!(!l.smushes_horizontal('_', '-', '$'));
+    assert_eq!(
+        l.vertical_rule('|', '|', '$'),
+        Some(SmushingRule::VerticalVerticalLine)
+    );
+
+    let universal = Rules::from_flags(LayoutFlags::VERTICAL_SMUSH);
+    assert_eq!(universal.vertical_layout, LayoutMode::UniversalSmush);
+    assert_eq!(universal.smush_vertical('/', '\\', '$'), Some('\\'));
+    assert_eq!(universal.smush_vertical('/', '$', '$'), None);
+}
+
+#[test]
+fn rule_set() {
+    let rules = RuleSet::new()
+        .enable(SmushingRule::HorizontalEqualChar)
+        .enable(SmushingRule::HorizontalBigX)
+        .build();
+    assert_eq!(rules, "bigx, equal".parse().unwrap());
+    assert_eq!(rules.horizontal_layout, LayoutMode::ControlledSmush);
+    assert_eq!(rules.vertical_layout, LayoutMode::FullWidth);
+
+    let fitting = RuleSet::new()
+        .enable(SmushingRule::HorizontalEqualChar)
+        .horizontal_mode(LayoutMode::Fitting)
+        .build();
+    assert_eq!(fitting.horizontal_rules, [SmushingRule::HorizontalFitting]);
+    let universal = RuleSet::new().horizontal_mode(LayoutMode::ControlledSmush);
+    assert_eq!(
+        universal.build().horizontal_layout,
+        LayoutMode::UniversalSmush
+    );
+
+    let standard = crate::font::Font::load_font("Standard.flf").unwrap();
+    let set = RuleSet::from_rules(standard.layout());
+    assert_eq!(set.build(), *standard.layout());
+    let fewer = Rules::from(set.disable(SmushingRule::HorizontalHierarchy));
+    assert!(!fewer
+        .horizontal_rules
+        .contains(&SmushingRule::HorizontalHierarchy));
+    assert_eq!(fewer.horizontal_layout, LayoutMode::ControlledSmush);
+}
@@ -0,0 +1,195 @@
+use crate::color::{Color, Theme};
+use crate::error::SettingsError;
+use crate::filter::{Bold, HalfHeight, Inverse, Line, Outline, Scale, Shadow};
+use crate::font::Font;
+use crate::render::{Align, Renderer};
+use crate::rules::Rules;
+
+/// A post-processing filter as stored in `RenderSettings`, e.g.
+/// `{ name = "shadow", dx = 1, dy = 1, fill = "." }` in TOML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "name", rename_all = "kebab-case")
+)]
+pub enum FilterSetting {
+    Shadow { dx: isize, dy: isize, fill: char },
+    Bold,
+    Underline { fill: char },
+    Strikethrough { fill: char },
+    Scale { sx: usize, sy: usize },
+    HalfHeight,
+    Outline,
+    Inverse { fill: char },
+}
+
+/// A banner preset: the font and how to lay out and color text in it,
+/// for applications to store and share. Everything but the font is
+/// optional and left to the renderer's defaults when missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct RenderSettings {
+    /// A font name as `Font::load_font` takes it, e.g. "slant".
+    pub font: String,
+    pub width: Option<usize>,
+    /// Layout rules as `Rules` parses them, e.g. "smush, equal, hierarchy".
+    pub layout: Option<String>,
+    pub align: Option<Align>,
+    /// A built-in theme's name, or one color such as "red" or "#ff8800".
+    pub theme: Option<String>,
+    pub filters: Vec<FilterSetting>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            font: String::from("Standard.flf"),
+            width: None,
+            layout: None,
+            align: None,
+            theme: None,
+            filters: vec![],
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Read settings from TOML such as `font = "slant"` / `width = 60`.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(data: &str) -> Result<Self, SettingsError> {
+        Ok(toml::from_str(data)?)
+    }
+
+    /// Read settings from a JSON object with the same fields as in TOML.
+    #[cfg(feature = "json")]
+    pub fn from_json(data: &str) -> Result<Self, SettingsError> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    pub fn load_font(&self) -> Result<Font, SettingsError> {
+        Ok(Font::load_font(&self.font)?)
+    }
+
+    /// A renderer for `font`, usually from `load_font`, set up as these
+    /// settings say. Colors aren't part of rendering; see `theme`.
+    pub fn renderer<'a>(&self, font: &'a Font) -> Result<Renderer<'a>, SettingsError> {
+        let mut renderer = Renderer::new(font);
+        if let Some(width) = self.width {
+            renderer = renderer.width(width);
+        }
+        if let Some(layout) = &self.layout {
+            renderer = renderer.rules(layout.parse::<Rules>()?);
+        }
+        if let Some(align) = self.align {
+            renderer = renderer.align(align);
+        }
+        for filter in self.filters.iter() {
+            renderer = match *filter {
+                FilterSetting::Shadow { dx, dy, fill } => {
+                    renderer.filter(Shadow::new(dx, dy, fill))
+                }
+                FilterSetting::Bold => renderer.filter(Bold),
+                FilterSetting::Underline { fill } => renderer.filter(Line::underline(fill)),
+                FilterSetting::Strikethrough { fill } => {
+                    renderer.filter(Line::strikethrough(font, fill))
+                }
+                FilterSetting::Scale { sx, sy } => renderer.filter(Scale::new(sx, sy)),
+                FilterSetting::HalfHeight => renderer.filter(HalfHeight),
+                FilterSetting::Outline => renderer.filter(Outline),
+                FilterSetting::Inverse { fill } => renderer.filter(Inverse::new(fill)),
+            };
+        }
+        Ok(renderer)
+    }
+
+    /// The theme to paint banners with, if any.
+    pub fn theme(&self) -> Result<Option<Theme>, SettingsError> {
+        let name = match &self.theme {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        if let Some(theme) = Theme::builtin(name) {
+            return Ok(Some(theme));
+        }
+        match Color::from_name(name).or_else(|| Color::from_hex(name)) {
+            Some(color) => Ok(Some(Theme::new(name, vec![color]))),
+            None => Err(SettingsError::UnknownTheme(name.clone())),
+        }
+    }
+}
+
+#[test]
+fn settings_renderer() {
+    let settings = RenderSettings {
+        font: String::from("slant"),
+        width: Some(30),
+        layout: Some(String::from("fitting")),
+        filters: vec![FilterSetting::Bold],
+        ..RenderSettings::default()
+    };
+    let font = settings.load_font().unwrap();
+    assert_eq!(font.name, "Slant.flf");
+    let expected = Renderer::new(&font)
+        .width(30)
+        .rules("fitting".parse().unwrap())
+        .filter(Bold)
+        .render("Hi there");
+    assert_eq!(
+        settings.renderer(&font).unwrap().render("Hi there"),
+        expected
+    );
+
+    let bad = RenderSettings {
+        layout: Some(String::from("sideways")),
+        ..RenderSettings::default()
+    };
+    assert!(matches!(bad.renderer(&font), Err(SettingsError::Layout(_))));
+    assert_eq!(RenderSettings::default().theme().unwrap(), None);
+    let red = RenderSettings {
+        theme: Some(String::from("red")),
+        ..RenderSettings::default()
+    };
+    assert_eq!(
+        red.theme().unwrap().unwrap().palette,
+        [Color::from_name("red").unwrap()]
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn settings_from_toml() {
+    let settings = RenderSettings::from_toml(
+        "font = \"big\"\nalign = \"center\"\nfilters = [{ name = \"shadow\", dx = 1, dy = 1, fill = \".\" }, { name = \"half-height\" }]",
+    )
+    .unwrap();
+    assert_eq!(settings.font, "big");
+    assert_eq!(settings.align, Some(Align::Center));
+    assert_eq!(
+        settings.filters,
+        [
+            FilterSetting::Shadow {
+                dx: 1,
+                dy: 1,
+                fill: '.'
+            },
+            FilterSetting::HalfHeight
+        ]
+    );
+    assert!(RenderSettings::from_toml("size = 3").is_err());
+    let text = toml::to_string(&settings).unwrap();
+    assert_eq!(RenderSettings::from_toml(&text).unwrap(), settings);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn settings_from_json() {
+    let settings = RenderSettings::from_json(r#"{"font": "small", "width": 40}"#).unwrap();
+    assert_eq!(settings.font, "small");
+    assert_eq!(settings.width, Some(40));
+    assert_eq!(settings.layout, None);
+}
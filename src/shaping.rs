@@ -0,0 +1,169 @@
+use std::borrow::Cow;
+
+/// How an Arabic letter connects to the letters around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Joining {
+    /// Never connects, like hamza.
+    Isolated,
+    /// Connects only to the letter before it, like alef.
+    Right,
+    /// Connects on both sides, like beh.
+    Dual,
+    /// Tatweel: forces its neighbours to connect but has no forms itself.
+    Causing,
+}
+
+/// Joining types of U+0621..=U+063A and U+0641..=U+064A, in code order.
+const LETTERS_1: &str = "IRRRRDRDRDDDDDRRRRDDDDDDDD";
+const LETTERS_2: &str = "DDDDDDDRRD";
+
+/// The joining type of `c` and, for letters, the code of its isolated
+/// presentation form. The other forms follow it in the order final,
+/// initial, medial, as far as the letter has them.
+fn joining(c: char) -> Option<(Joining, u32)> {
+    let code = c as u32;
+    if code == 0x640 {
+        return Some((Joining::Causing, 0));
+    }
+    let index = match code {
+        0x621..=0x63a => (code - 0x621) as usize,
+        0x641..=0x64a => LETTERS_1.len() + (code - 0x641) as usize,
+        _ => return None,
+    };
+    let mut form = 0xfe80;
+    for (i, kind) in LETTERS_1.chars().chain(LETTERS_2.chars()).enumerate() {
+        let kind = match kind {
+            'I' => Joining::Isolated,
+            'R' => Joining::Right,
+            _ => Joining::Dual,
+        };
+        if i == index {
+            return Some((kind, form));
+        }
+        form += match kind {
+            Joining::Isolated => 1,
+            Joining::Right => 2,
+            _ => 4,
+        };
+    }
+    None
+}
+
+/// Harakat and other marks that sit on a letter without breaking joins.
+fn is_transparent(c: char) -> bool {
+    matches!(c as u32, 0x64b..=0x65f | 0x670)
+}
+
+/// Whether `c` belongs to a right-to-left script.
+pub(crate) fn is_rtl(c: char) -> bool {
+    matches!(c as u32, 0x590..=0x8ff | 0xfb1d..=0xfdff | 0xfe70..=0xfeff)
+}
+
+/// Whether the first letter of `message` is from a right-to-left script.
+pub(crate) fn starts_rtl(message: &str) -> bool {
+    message
+        .chars()
+        .find(|c| c.is_alphabetic())
+        .is_some_and(is_rtl)
+}
+
+/// Replace Arabic letters with the initial, medial, final or isolated
+/// presentation form their neighbours call for, wherever `has_glyph` says
+/// the font draws that form.
+pub(crate) fn shape_arabic<F: Fn(char) -> bool>(message: &str, has_glyph: F) -> Cow<'_, str> {
+    if !message.chars().any(|c| joining(c).is_some()) {
+        return Cow::Borrowed(message);
+    }
+    let chars: Vec<char> = message.chars().collect();
+    let neighbour = |range: &mut dyn Iterator<Item = usize>| {
+        range
+            .map(|i| chars[i])
+            .find(|c| !is_transparent(*c))
+            .and_then(joining)
+            .map(|(kind, _)| kind)
+    };
+    let mut result = String::with_capacity(message.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let (kind, isolated) = match joining(c) {
+            Some((Joining::Causing, _)) | None => {
+                result.push(c);
+                continue;
+            }
+            Some(letter) => letter,
+        };
+        let prev = neighbour(&mut (0..i).rev());
+        let next = neighbour(&mut (i + 1..chars.len()));
+        let joins_prev =
+            kind != Joining::Isolated && matches!(prev, Some(Joining::Dual | Joining::Causing));
+        let joins_next = kind == Joining::Dual
+            && matches!(
+                next,
+                Some(Joining::Right | Joining::Dual | Joining::Causing)
+            );
+        let offset = match (joins_prev, joins_next) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        match char::from_u32(isolated + offset) {
+            Some(form) if has_glyph(form) => result.push(form),
+            _ => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// The units text is drawn in: with the `graphemes` feature, extended
+/// grapheme clusters, so a letter with combining marks, a flag or a joined
+/// emoji sequence is looked up and replaced as one; otherwise single chars.
+pub(crate) fn clusters(text: &str) -> impl Iterator<Item = &str> {
+    #[cfg(feature = "graphemes")]
+    {
+        unicode_segmentation::UnicodeSegmentation::graphemes(text, true)
+    }
+    #[cfg(not(feature = "graphemes"))]
+    {
+        text.char_indices()
+            .map(move |(i, c)| &text[i..i + c.len_utf8()])
+    }
+}
+
+#[test]
+fn arabic_forms() {
+    let all = |_| true;
+    // beh beh alef: initial beh, medial beh, final alef
+    assert_eq!(
+        shape_arabic("\u{628}\u{628}\u{627}", all),
+        "\u{fe91}\u{fe92}\u{fe8e}"
+    );
+    // alef doesn't join forward, so the beh after it starts a new word form
+    assert_eq!(
+        shape_arabic("\u{627}\u{628} \u{628}", all),
+        "\u{fe8d}\u{fe8f} \u{fe8f}"
+    );
+    // marks are skipped when looking for neighbours
+    assert_eq!(
+        shape_arabic("\u{644}\u{64e}\u{627}", all),
+        "\u{fedf}\u{64e}\u{fe8e}"
+    );
+    // forms the font lacks keep the base letter
+    assert_eq!(
+        shape_arabic("\u{628}\u{628}", |c| c == '\u{fe90}'),
+        "\u{628}\u{fe90}"
+    );
+    assert!(matches!(shape_arabic("abc", all), Cow::Borrowed("abc")));
+    assert!(starts_rtl("123 \u{5d0}b"));
+    assert!(!starts_rtl("a\u{5d0}"));
+}
+
+#[test]
+fn text_clusters() {
+    let units: Vec<&str> = clusters("ab\u{e9}").collect();
+    assert_eq!(units, ["a", "b", "\u{e9}"]);
+    #[cfg(feature = "graphemes")]
+    {
+        let units: Vec<&str> = clusters("e\u{301}\u{1f1fa}\u{1f1f8}!").collect();
+        assert_eq!(units, ["e\u{301}", "\u{1f1fa}\u{1f1f8}", "!"]);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::figure::FIGure;
+use crate::filter::{Filter, HalfHeight};
+use crate::font::Font;
+
+/// Screen size assumed when the terminal can't be asked.
+pub const DEFAULT_SIZE: (usize, usize) = (80, 24);
+
+/// The terminal's size in columns and lines, from the terminal itself with
+/// the `crossterm` feature, otherwise from `$COLUMNS` and `$LINES`.
+pub fn terminal_size() -> Option<(usize, usize)> {
+    #[cfg(feature = "crossterm")]
+    if let Ok((columns, lines)) = crossterm::terminal::size() {
+        return Some((columns as usize, lines as usize));
+    }
+    let var = |name| std::env::var(name).ok()?.parse().ok();
+    Some((var("COLUMNS")?, var("LINES")?))
+}
+
+/// The figure centered in a screen of `columns` by `lines`, as text with
+/// blank lines above it and every row indented. A figure bigger than the
+/// screen is placed at the top left.
+pub fn center(figure: &FIGure, columns: usize, lines: usize) -> String {
+    let top = lines.saturating_sub(figure.height()) / 2;
+    let left = columns.saturating_sub(figure.width()) / 2;
+    let indent = " ".repeat(left);
+    let mut text = "\n".repeat(top);
+    for (i, row) in figure.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        text.push_str(&indent);
+        text.push_str(row);
+    }
+    text
+}
+
+/// The figure centered on the terminal, falling back to `DEFAULT_SIZE`,
+/// and with `clear` behind an escape that clears the screen first.
+pub fn splash(figure: &FIGure, clear: bool) -> String {
+    let (columns, lines) = terminal_size().unwrap_or(DEFAULT_SIZE);
+    let text = center(figure, columns, lines);
+    if clear {
+        format!("\x1b[2J\x1b[H{}", text)
+    } else {
+        text
+    }
+}
+
+/// Draw the figure centered on the terminal through crossterm, clearing
+/// the screen first when `clear` is set.
+#[cfg(feature = "crossterm")]
+pub fn show(figure: &FIGure, clear: bool) -> std::io::Result<()> {
+    use crossterm::{cursor, queue, style, terminal};
+    use std::io::Write;
+
+    let (columns, lines) = terminal::size()?;
+    let top = (lines as usize).saturating_sub(figure.height()) / 2;
+    let left = (columns as usize).saturating_sub(figure.width()) / 2;
+    let mut out = std::io::stdout();
+    if clear {
+        queue!(out, terminal::Clear(terminal::ClearType::All))?;
+    }
+    for (y, row) in figure.iter().enumerate() {
+        queue!(
+            out,
+            cursor::MoveTo(left as u16, (top + y) as u16),
+            style::Print(row)
+        )?;
+    }
+    out.flush()
+}
+
+/// What `fit_height` tries when a banner has too many rows.
+#[derive(Debug, Clone, Copy)]
+pub enum Fallback<'a> {
+    /// Render in another font, usually a smaller one.
+    Font(&'a Font),
+    /// Squeeze the first font's banner with the `HalfHeight` filter.
+    HalfHeight,
+}
+
+/// `message` in `font` if the banner fits in `lines` rows, such as the
+/// second half of `terminal_size`, or else in the first of `fallbacks` that
+/// fits. When none does, the last one is used anyway.
+pub fn fit_height(font: &Font, message: &str, lines: usize, fallbacks: &[Fallback]) -> FIGure {
+    let mut figure = font.render(message);
+    for fallback in fallbacks.iter() {
+        if figure.height() <= lines {
+            break;
+        }
+        figure = match fallback {
+            Fallback::Font(smaller) => smaller.render(message),
+            Fallback::HalfHeight => HalfHeight.apply(font.render(message)),
+        };
+    }
+    figure
+}
+
+#[test]
+fn centered() {
+    let figure = FIGure::new(vec!["/\\".chars().collect(), "\\/".chars().collect()]);
+    assert_eq!(center(&figure, 6, 5), "\n  /\\\n  \\/");
+    assert_eq!(center(&figure, 1, 1), "/\\\n\\/");
+    assert!(splash(&figure, true).starts_with("\x1b[2J\x1b[H"));
+}
+
+#[test]
+fn fallbacks() {
+    let big = Font::load_font("Big.flf").unwrap();
+    let small = Font::load_font("Small.flf").unwrap();
+    let fallbacks = [Fallback::Font(&small), Fallback::HalfHeight];
+    assert_eq!(fit_height(&big, "Hi", 24, &fallbacks), big.render("Hi"));
+    let height = small.render("Hi").height();
+    assert_eq!(
+        fit_height(&big, "Hi", height, &fallbacks),
+        small.render("Hi")
+    );
+    assert_eq!(
+        fit_height(&big, "Hi", height - 1, &fallbacks),
+        HalfHeight.apply(big.render("Hi"))
+    );
+    assert_eq!(fit_height(&big, "Hi", 1, &[]), big.render("Hi"));
+}
@@ -0,0 +1,115 @@
+use crate::figure::FIGure;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// When this environment variable is set, `assert_golden` writes the
+/// figure as the new golden instead of comparing against it.
+pub const UPDATE_VAR: &str = "FIGLET_UPDATE_GOLDEN";
+
+/// The golden file contents for `figure`: each row, trailing spaces and
+/// all, followed by a newline.
+pub fn golden_text(figure: &FIGure) -> String {
+    figure.iter().map(|row| format!("{}\n", row)).collect()
+}
+
+/// Write `figure` to `path` as a golden, creating missing directories.
+pub fn write_golden(figure: &FIGure, path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, golden_text(figure))
+}
+
+/// A row-by-row comparison of two pieces of ASCII art, or `None` if they
+/// are the same. Rows that differ are shown as `-` expected and `+` actual,
+/// ended by `|` so trailing spaces can be seen, with a `^` under the first
+/// column where they part.
+pub fn diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut report = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => report.push_str(&format!("  {}|\n", e)),
+            (e, a) => {
+                if let Some(e) = e {
+                    report.push_str(&format!("- {}|\n", e));
+                }
+                if let Some(a) = a {
+                    report.push_str(&format!("+ {}|\n", a));
+                }
+                if let (Some(e), Some(a)) = (e, a) {
+                    let column = e.chars().zip(a.chars()).take_while(|(e, a)| e == a).count();
+                    report.push_str(&format!("  {}^\n", " ".repeat(column)));
+                }
+            }
+        }
+    }
+    Some(report)
+}
+
+/// Panic with a readable diff unless `figure` matches the golden file at
+/// `path`. With `FIGLET_UPDATE_GOLDEN` set, the golden is (re)written from
+/// `figure` instead, which is also how new goldens are made.
+#[track_caller]
+pub fn assert_golden(figure: &FIGure, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    if env::var_os(UPDATE_VAR).is_some() {
+        if let Err(e) = write_golden(figure, path) {
+            panic!("{}: {}", path.display(), e);
+        }
+        return;
+    }
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "{}: {} (set {}=1 to create it)",
+            path.display(),
+            e,
+            UPDATE_VAR
+        ),
+    };
+    // Goldens checked out on Windows may have gained carriage returns.
+    let expected = expected.replace("\r\n", "\n");
+    if let Some(report) = diff(&expected, &golden_text(figure)) {
+        panic!(
+            "{} does not match (set {}=1 to update it):\n{}",
+            path.display(),
+            UPDATE_VAR,
+            report
+        );
+    }
+}
+
+#[test]
+fn golden_diff() {
+    assert_eq!(diff(" _\n|_\n", " _\n|_\n"), None);
+    assert_eq!(
+        diff(" _ \n|_|\n", " _ \n|_ \n"),
+        Some("   _ |\n- |_||\n+ |_ |\n    ^\n".to_string())
+    );
+    assert_eq!(diff("a\n", "a\nb\n"), Some("  a|\n+ b|\n".to_string()));
+}
+
+#[test]
+fn golden_files() {
+    let f = crate::font::Font::load_font("Standard.flf").unwrap();
+    let path = env::temp_dir()
+        .join(format!("figlet-golden-{}", std::process::id()))
+        .join("hi.txt");
+    write_golden(&f.render("Hi"), &path).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        format!("{}\n", f.render("Hi"))
+    );
+    assert_golden(&f.render("Hi"), &path);
+    let mismatch = std::panic::catch_unwind(|| assert_golden(&f.render("Ho"), &path));
+    assert!(mismatch.is_err());
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
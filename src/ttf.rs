@@ -0,0 +1,211 @@
+use crate::error::FontError;
+use crate::font::{Font, DEUTSCH_CODES};
+
+/// How `generate` turns a TrueType or OpenType font into a FIGfont.
+#[derive(Debug, Clone)]
+pub struct TtfOptions {
+    pixel_height: f32,
+    threshold: u8,
+    fill: Vec<char>,
+    chars: Vec<char>,
+}
+
+impl TtfOptions {
+    /// Rasterize at `pixel_height` pixels per em, one pixel per cell.
+    pub fn new(pixel_height: f32) -> Self {
+        TtfOptions {
+            pixel_height,
+            threshold: 128,
+            fill: vec!['#'],
+            chars: (32..=126)
+                .chain(DEUTSCH_CODES.iter().cloned())
+                .filter_map(|code| char::from_u32(code as u32))
+                .collect(),
+        }
+    }
+
+    /// The coverage, out of 255, from which a pixel counts as ink.
+    pub fn threshold(mut self, threshold: u8) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// The characters ink is drawn with, from faintest to fullest
+    /// coverage, e.g. "#" or ".:#".
+    pub fn fill(mut self, fill: &str) -> Self {
+        self.fill = fill.chars().collect();
+        self
+    }
+
+    /// The characters to generate glyphs for, by default printable ASCII
+    /// and the Deutsch characters every FIGfont has.
+    pub fn chars(mut self, chars: impl IntoIterator<Item = char>) -> Self {
+        self.chars = chars.into_iter().collect();
+        self
+    }
+
+    /// The character for a pixel with `coverage`.
+    fn shade(&self, coverage: u8) -> char {
+        if coverage < self.threshold || self.fill.is_empty() {
+            return ' ';
+        }
+        let span = 256 - self.threshold as usize;
+        let level = (coverage - self.threshold) as usize * self.fill.len() / span;
+        self.fill[level.min(self.fill.len() - 1)]
+    }
+}
+
+/// A FIGfont, in `.flf` form, drawn from the TrueType or OpenType font in
+/// `data`. The layout is full width, so letters keep the spacing the
+/// font's own advance widths give them.
+pub fn generate_flf(name: &str, data: &[u8], options: &TtfOptions) -> Result<String, FontError> {
+    let ttf = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())
+        .map_err(|e| FontError::Ttf(e.to_string()))?;
+    let px = options.pixel_height;
+    let line = ttf
+        .horizontal_line_metrics(px)
+        .ok_or_else(|| FontError::Ttf(String::from("font has no horizontal metrics")))?;
+    let ascent = line.ascent.ceil() as i32;
+    let height = (ascent + (-line.descent).ceil() as i32).max(1) as usize;
+    // The hardblank and end mark can't be characters the art uses.
+    let free = |candidates: &str| {
+        candidates
+            .chars()
+            .find(|c| !options.fill.contains(c))
+            .unwrap_or('\u{7f}')
+    };
+    let hardblank = free("$~^");
+    let endmark = free("@#%&");
+
+    let glyph = |c: char| -> Vec<String> {
+        if !options.chars.contains(&c) || (c != ' ' && !ttf.has_glyph(c)) {
+            return vec![String::new(); height];
+        }
+        let (metrics, bitmap) = ttf.rasterize(c, px);
+        if c == ' ' {
+            let width = (metrics.advance_width.round() as usize).max(1);
+            return vec![hardblank.to_string().repeat(width); height];
+        }
+        draw(&bitmap, &metrics, ascent, height, options)
+    };
+    let mut glyphs: Vec<(i32, Vec<String>)> = (32..=126)
+        .chain(DEUTSCH_CODES.iter().cloned())
+        .filter_map(|code| Some((code, glyph(char::from_u32(code as u32)?))))
+        .collect();
+    let required = glyphs.len();
+    let mut tagged: Vec<char> = options
+        .chars
+        .iter()
+        .cloned()
+        .filter(|&c| !glyphs.iter().any(|(code, _)| *code == c as i32) && ttf.has_glyph(c))
+        .collect();
+    tagged.sort_unstable();
+    tagged.dedup();
+    glyphs.extend(tagged.iter().map(|&c| (c as i32, glyph(c))));
+
+    let max_length = glyphs
+        .iter()
+        .flat_map(|(_, rows)| rows.iter().map(|row| row.chars().count()))
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let comment = format!("Generated from {} at {}px", name, px);
+    let mut flf = format!(
+        "flf2a{} {} {} {} -1 1 0 0 {}\n{}\n",
+        hardblank,
+        height,
+        ascent.max(1),
+        max_length,
+        tagged.len(),
+        comment
+    );
+    for (i, (code, rows)) in glyphs.iter().enumerate() {
+        if i >= required {
+            flf.push_str(&format!("{}\n", code));
+        }
+        for (y, row) in rows.iter().enumerate() {
+            let marks = if y + 1 == rows.len() { 2 } else { 1 };
+            flf.push_str(row);
+            flf.extend(std::iter::repeat_n(endmark, marks));
+            flf.push('\n');
+        }
+    }
+    Ok(flf)
+}
+
+/// Like `generate_flf`, but parsed into a `Font` ready to render with.
+pub fn generate(name: &str, data: &[u8], options: &TtfOptions) -> Result<Font, FontError> {
+    Font::parse_font(name, &generate_flf(name, data, options)?)
+}
+
+/// The rows of a glyph `height` rows tall from its coverage `bitmap`, with
+/// the baseline under row `ascent`.
+fn draw(
+    bitmap: &[u8],
+    metrics: &fontdue::Metrics,
+    ascent: i32,
+    height: usize,
+    options: &TtfOptions,
+) -> Vec<String> {
+    // Ink left of the origin shifts the whole glyph right.
+    let left = metrics.xmin.min(0);
+    let width =
+        (metrics.advance_width.round() as i32).max(metrics.xmin + metrics.width as i32) - left;
+    let mut cells = vec![vec![' '; width.max(0) as usize]; height];
+    let top = ascent - (metrics.ymin + metrics.height as i32);
+    for (by, pixels) in bitmap.chunks(metrics.width.max(1)).enumerate() {
+        let y = top + by as i32;
+        if y < 0 || y as usize >= height {
+            continue;
+        }
+        for (bx, &coverage) in pixels.iter().enumerate() {
+            let x = (metrics.xmin - left) as usize + bx;
+            cells[y as usize][x] = options.shade(coverage);
+        }
+    }
+    cells.iter().map(|row| row.iter().collect()).collect()
+}
+
+#[test]
+fn shading() {
+    let options = TtfOptions::new(12.0).fill(".:#");
+    assert_eq!(options.shade(0), ' ');
+    assert_eq!(options.shade(127), ' ');
+    assert_eq!(options.shade(128), '.');
+    assert_eq!(options.shade(200), ':');
+    assert_eq!(options.shade(255), '#');
+}
+
+#[test]
+fn draw_bitmap() {
+    let metrics = fontdue::Metrics {
+        xmin: 1,
+        ymin: -1,
+        width: 2,
+        height: 3,
+        advance_width: 4.0,
+        ..fontdue::Metrics::default()
+    };
+    let bitmap = [255, 0, 255, 255, 0, 255];
+    let rows = draw(&bitmap, &metrics, 2, 4, &TtfOptions::new(4.0));
+    assert_eq!(rows, [" #  ", " ## ", "  # ", "    "]);
+}
+
+#[test]
+fn generate_from_ttf() {
+    // Only runs where DejaVu Sans is installed, as on most Linux systems.
+    let path = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(_) => return,
+    };
+    let options = TtfOptions::new(12.0).chars("Hi \u{e9}".chars());
+    let font = generate("DejaVuSans", &data, &options).unwrap();
+    assert_eq!(font.glyph_count(), 103);
+    assert!(font.glyph('H').unwrap().width() > 0);
+    assert_eq!(font.glyph('x').unwrap().width(), 0);
+    assert!(font.supports('\u{e9}'));
+    let banner = font.render("Hi").to_string();
+    assert!(banner.contains('#'));
+    assert!(generate("x", b"not a font", &options).is_err());
+}
@@ -0,0 +1,160 @@
+use crate::figure::FIGure;
+use crate::font::{self, Font};
+use crate::rules::Rules;
+use crate::shaping;
+use std::collections::HashMap;
+
+/// A font bound to one set of layout rules, with everything that doesn't
+/// depend on the message worked out up front: the outcome of smushing any
+/// two characters that meet at glyph edges. For servers rendering many
+/// messages with the same settings; the output matches `Font::render`.
+#[derive(Debug, Clone)]
+pub struct Typesetter<'a> {
+    font: &'a Font,
+    rules: Rules,
+    rtl: bool,
+    /// Whether a pair of ink characters can touch, and what they smush to.
+    smush: HashMap<(char, char), (bool, Option<char>)>,
+}
+
+impl<'a> Typesetter<'a> {
+    /// A typesetter using the font's own rules and print direction.
+    pub fn new(font: &'a Font) -> Self {
+        Typesetter::with_rules(font, font.rules.clone())
+    }
+
+    /// A typesetter laying glyphs out with `rules` instead of the font's.
+    pub fn with_rules(font: &'a Font, rules: Rules) -> Self {
+        let mut typesetter = Typesetter {
+            font,
+            rules,
+            rtl: font.font_head.print_direction() == 1,
+            smush: HashMap::new(),
+        };
+        // Every character that can end up at the edge of a glyph row.
+        let mut edges: Vec<char> = font
+            .chars
+            .values()
+            .flat_map(|glyph| glyph.rows().iter())
+            .flat_map(|row| {
+                let mut ink = row.iter().filter(|c| **c != ' ');
+                ink.next().into_iter().chain(ink.next_back())
+            })
+            .cloned()
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        for &left in edges.iter() {
+            for &right in edges.iter() {
+                let outcome = typesetter.resolve(left, right);
+                typesetter.smush.insert((left, right), outcome);
+            }
+        }
+        typesetter
+    }
+
+    pub fn font(&self) -> &'a Font {
+        self.font
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Render `message` as `Font::render` would with these rules.
+    pub fn render(&self, message: &str) -> FIGure {
+        let mut codes: Vec<i32> = shaping::clusters(message)
+            .map(|cluster| cluster.chars().next().map_or(0, |c| c as i32))
+            .filter_map(|code| match self.font.chars.contains_key(&code) {
+                true => Some(code),
+                false if self.font.chars.contains_key(&0) => Some(0),
+                false => None,
+            })
+            .collect();
+        if self.rtl {
+            codes.reverse();
+        }
+
+        let height = self.font.font_head.height();
+        let mut rows: Vec<Vec<char>> = vec![vec![]; height];
+        let mut trailing = vec![0; height];
+        let touches = |c1, c2| self.outcome(c1, c2).0;
+        let merge = |c1, c2| self.merge(c1, c2);
+        let mut prev_width = None;
+        for code in codes {
+            let glyph = &self.font.chars[&code];
+            let touches: &dyn Fn(char, char) -> bool = match font::narrow(prev_width, glyph) {
+                true => &|_, _| false,
+                false => &touches,
+            };
+            let overlay = font::margin_overlay(&self.rules, &rows, &trailing, glyph, touches);
+            let line = rows.iter().map(|row| row.len()).min().unwrap_or(0);
+            font::place(&mut rows, &mut trailing, glyph, overlay.min(line), &merge);
+            prev_width = Some(glyph.width());
+        }
+        let mut figure = FIGure::new(rows);
+        figure.replace(self.font.font_head.hardblank(), ' ');
+        figure
+    }
+
+    /// What two overlapping cells become, as in `font::merge`.
+    fn merge(&self, c1: char, c2: char) -> char {
+        if c1 == ' ' {
+            return c2;
+        }
+        if c2 == ' ' {
+            return c1;
+        }
+        if cfg!(feature = "compat") && self.rtl {
+            let hardblank = self.font.font_head.hardblank();
+            return font::merge(&self.rules, c1, c2, hardblank, true);
+        }
+        self.outcome(c1, c2).1.unwrap_or(c2)
+    }
+
+    fn outcome(&self, c1: char, c2: char) -> (bool, Option<char>) {
+        match self.smush.get(&(c1, c2)) {
+            Some(outcome) => *outcome,
+            None => self.resolve(c1, c2),
+        }
+    }
+
+    fn resolve(&self, c1: char, c2: char) -> (bool, Option<char>) {
+        let hardblank = self.font.font_head.hardblank();
+        (
+            font::touches(&self.rules, c1, c2, hardblank),
+            self.rules.smush_horizontal(c1, c2, hardblank),
+        )
+    }
+}
+
+#[test]
+fn matches_font_render() {
+    let messages = [
+        "Hello, World!",
+        "  a  b  ",
+        "",
+        "|/\\_[]{}()<>",
+        "\u{e9}\u{263a}",
+    ];
+    for name in [
+        "Standard.flf",
+        "Slant.flf",
+        "Big.flf",
+        "Ivrit.flf",
+        "Banner.flf",
+    ] {
+        let f = Font::load_font(name).unwrap();
+        let typesetter = Typesetter::new(&f);
+        for message in messages.iter() {
+            assert_eq!(typesetter.render(message), f.render(message), "{}", name);
+        }
+    }
+
+    let f = Font::load_font("Standard.flf").unwrap();
+    let kern: Rules = "kern".parse().unwrap();
+    assert_eq!(
+        Typesetter::with_rules(&f, kern.clone()).render("Hi"),
+        crate::render::Renderer::new(&f).rules(kern).render("Hi")
+    );
+}
@@ -0,0 +1,155 @@
+/// What to do with a word too wide to fit on a line of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapPolicy {
+    /// Break it between any two characters, as figlet does.
+    #[default]
+    Anywhere,
+    /// Only ever break at whitespace, and let the word overflow.
+    Words,
+    /// Fail instead of overflowing or breaking the word.
+    Error,
+}
+
+/// Split `message` into lines whose width, as given by `measure`, fits in
+/// `width` columns. Lines break at whitespace and at newlines in the message;
+/// `policy` decides what happens to a word too long for a line of its
+/// own, after trying its hyphenation points when `hyphenate` is set. With
+/// `WrapPolicy::Error` the offending word is returned as the error.
+pub(crate) fn wrap(
+    measure: &dyn Fn(&str) -> usize,
+    message: &str,
+    width: usize,
+    policy: WrapPolicy,
+    hyphenate: bool,
+) -> Result<Vec<String>, String> {
+    let fits = |line: &str| measure(line) <= width;
+    let mut lines = vec![];
+    for paragraph in message.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let breaks = if hyphenate {
+                hyphenation_points(word)
+            } else {
+                vec![]
+            };
+            let mut start = 0;
+            loop {
+                let rest = &word[start..];
+                let candidate = join(&line, rest);
+                if fits(&candidate) {
+                    line = candidate;
+                    break;
+                }
+                // Fill the line up to the last hyphenation point that fits.
+                let hyphen = breaks
+                    .iter()
+                    .rev()
+                    .filter(|b| **b > start)
+                    .find(|b| fits(&join(&line, &format!("{}-", &word[start..**b]))));
+                if let Some(&b) = hyphen {
+                    lines.push(join(&line, &format!("{}-", &word[start..b])));
+                    line.clear();
+                    start = b;
+                    continue;
+                }
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                    continue;
+                }
+                // Alone on a line and still too wide.
+                match policy {
+                    WrapPolicy::Anywhere => {}
+                    WrapPolicy::Words => {
+                        line = rest.to_string();
+                        break;
+                    }
+                    WrapPolicy::Error => return Err(word.to_string()),
+                }
+                // Break anywhere, but always take at least one character.
+                let mut end = start + rest.chars().next().map_or(0, char::len_utf8);
+                for (i, c) in rest.char_indices().skip(1) {
+                    if !fits(&word[start..start + i + c.len_utf8()]) {
+                        break;
+                    }
+                    end = start + i + c.len_utf8();
+                }
+                if end == word.len() {
+                    line = rest.to_string();
+                    break;
+                }
+                lines.push(word[start..end].to_string());
+                start = end;
+            }
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+fn join(line: &str, word: &str) -> String {
+    if line.is_empty() {
+        word.to_string()
+    } else {
+        format!("{} {}", line, word)
+    }
+}
+
+/// Byte offsets in `word` where it may be hyphenated.
+#[cfg(feature = "hyphenation")]
+fn hyphenation_points(word: &str) -> Vec<usize> {
+    use hyphenation::{Hyphenator, Language, Load, Standard};
+    use std::sync::OnceLock;
+
+    static DICTIONARY: OnceLock<Option<Standard>> = OnceLock::new();
+    match DICTIONARY.get_or_init(|| Standard::from_embedded(Language::EnglishUS).ok()) {
+        Some(dictionary) => dictionary.hyphenate(word).breaks,
+        None => vec![],
+    }
+}
+
+#[cfg(not(feature = "hyphenation"))]
+fn hyphenation_points(_word: &str) -> Vec<usize> {
+    vec![]
+}
+
+#[test]
+fn wrap_words() {
+    let f = crate::font::Font::load_font("Standard.flf").unwrap();
+    let measure = |line: &str| f.render(line).width();
+    let width = f.render("world").width().max(f.render("Hello").width());
+    let wrap = |message, width, policy| wrap(&measure, message, width, policy, false);
+    assert_eq!(
+        wrap("Hello world\nHi", width, WrapPolicy::Anywhere),
+        Ok(vec![
+            "Hello".to_string(),
+            "world".to_string(),
+            "Hi".to_string()
+        ])
+    );
+    let narrow = f.render("Hel").width();
+    assert_eq!(
+        wrap("Hello", narrow, WrapPolicy::Anywhere),
+        Ok(vec!["Hel".to_string(), "lo".to_string()])
+    );
+    assert_eq!(wrap("Hello", 1, WrapPolicy::Anywhere).unwrap().len(), 5);
+    assert_eq!(
+        wrap("Hi Hello", narrow, WrapPolicy::Words),
+        Ok(vec!["Hi".to_string(), "Hello".to_string()])
+    );
+    assert_eq!(
+        wrap("Hi Hello", narrow, WrapPolicy::Error),
+        Err("Hello".to_string())
+    );
+}
+
+#[cfg(feature = "hyphenation")]
+#[test]
+fn wrap_hyphenated() {
+    let f = crate::font::Font::load_font("Standard.flf").unwrap();
+    let measure = |line: &str| f.render(line).width();
+    let width = f.render("hyphen-").width();
+    assert_eq!(
+        wrap(&measure, "hyphenation", width, WrapPolicy::Error, true),
+        Ok(vec!["hyphen-".to_string(), "ation".to_string()])
+    );
+}
@@ -0,0 +1,44 @@
+use std::process::Command;
+
+fn figlet(args: &[&str]) -> (String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_figlet"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn json_unsupported_character() {
+    let (stdout, stderr) = figlet(&["--json", "a\u{1f680}b"]);
+    assert!(stdout.starts_with("{\"font\":\"Standard.flf\",\"rows\":["));
+    assert_eq!(
+        stderr,
+        "{\"warning\":{\"code\":\"unsupported\",\
+         \"message\":\"Standard.flf has no glyph for '\u{1f680}' at byte 1\",\
+         \"character\":\"\u{1f680}\",\"offset\":1,\"font\":\"Standard.flf\"}}\n"
+    );
+    // `-q` drops the banner, not the warnings.
+    let (quiet, quiet_stderr) = figlet(&["--json", "-q", "a\u{1f680}b"]);
+    assert_eq!(quiet, "");
+    assert_eq!(quiet_stderr, stderr);
+}
+
+#[test]
+fn plain_unsupported_character() {
+    let (stdout, stderr) = figlet(&["a\u{1f680}b"]);
+    assert_eq!(stdout, figlet(&["ab"]).0);
+    assert!(!stdout.is_empty());
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn json_errors() {
+    let (stdout, stderr) = figlet(&["--json", "-f", "nosuchfont", "hi"]);
+    assert_eq!(stdout, "");
+    assert!(stderr.starts_with("{\"error\":{\"code\":\"font\","));
+    assert!(stderr.contains("\"font\":\"nosuchfont\""));
+}